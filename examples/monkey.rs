@@ -27,7 +27,8 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use genmesh::{Triangulate, MapToVertices};
-use rusterize::{Frame, Fragment, Raster};
+use rusterize::{Frame, Fragment, WideFragment, Raster};
+use rusterize::f32x8::f32x8x8;
 use image::{ImageBuffer, Rgba};
 use cgmath::*;
 use time::precise_time_s;
@@ -114,14 +115,24 @@ fn main() {
                 light_normal: Vector4<f32>
             }
 
-            impl Fragment<([f32; 4], [f32; 3])> for V {
+            impl WideFragment<([f32x8x8; 4], [f32x8x8; 3])> for V {
                 type Color = Rgba<u8>;
 
                 #[inline]
-                fn fragment(&self, (_, n) : ([f32; 4], [f32; 3])) -> Rgba<u8> {
-                    let normal = Vector4::new(n[0], n[1], n[2], 0.);
-                    let v = self.kd.mul_s(self.light_normal.dot(&normal).partial_max(0.)) + self.ka;
-                    Rgba([v.x as u8, v.y as u8, v.z as u8, 255])
+                fn fragment_wide(&self, (_, n): ([f32x8x8; 4], [f32x8x8; 3]),
+                                 mask: u64, color: &mut [Rgba<u8>; 64]) -> u64 {
+                    // the lighting term is computed for the whole tile at once
+                    let l = self.light_normal;
+                    let d = (n[0] * l.x + n[1] * l.y + n[2] * l.z).to_array();
+
+                    let mut m = mask;
+                    while m != 0 {
+                        let i = m.trailing_zeros() as usize;
+                        m &= !(1 << i);
+                        let v = self.kd.mul_s(d[i].partial_max(0.)) + self.ka;
+                        color[i] = Rgba([v.x as u8, v.y as u8, v.z as u8, 255]);
+                    }
+                    mask
                 }
             }
 
@@ -143,7 +154,7 @@ fn main() {
             let start = precise_time_s();
             frame.clear(Rgba([0u8, 0, 0, 0]));
             if !raster_order {
-                frame.raster(vertex, V{ka: ka, kd: kd, light_normal: light_normal});
+                frame.raster_wide(vertex, V{ka: ka, kd: kd, light_normal: light_normal});
             } else {
                 frame.raster(vertex.vertex(|(p, _)| { p }), RO{v: Arc::new(AtomicUsize::new(0))});
             }
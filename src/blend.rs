@@ -0,0 +1,322 @@
+use image::Rgba;
+
+/// The weight applied to the source or destination colour
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha
+}
+
+impl BlendFactor {
+    #[inline]
+    fn weight(self, src: &[f32; 4], dst: &[f32; 4], i: usize) -> f32 {
+        match self {
+            BlendFactor::Zero => 0.,
+            BlendFactor::One => 1.,
+            BlendFactor::SrcColor => src[i],
+            BlendFactor::OneMinusSrcColor => 1. - src[i],
+            BlendFactor::DstColor => dst[i],
+            BlendFactor::OneMinusDstColor => 1. - dst[i],
+            BlendFactor::SrcAlpha => src[3],
+            BlendFactor::OneMinusSrcAlpha => 1. - src[3],
+            BlendFactor::DstAlpha => dst[3],
+            BlendFactor::OneMinusDstAlpha => 1. - dst[3]
+        }
+    }
+}
+
+/// How the weighted source and destination are combined
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Equation {
+    /// src * sf + dst * df
+    Add,
+    /// src * sf - dst * df
+    Subtract,
+    /// dst * df - src * sf
+    ReverseSubtract,
+    /// min(src, dst), the factors are ignored
+    Min,
+    /// max(src, dst), the factors are ignored
+    Max
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlendChannel {
+    pub equation: Equation,
+    pub source: BlendFactor,
+    pub destination: BlendFactor
+}
+
+impl BlendChannel {
+    #[inline]
+    fn apply(&self, src: &[f32; 4], dst: &[f32; 4], i: usize) -> f32 {
+        let (s, d) = (src[i], dst[i]);
+        match self.equation {
+            Equation::Add => s * self.source.weight(src, dst, i) +
+                             d * self.destination.weight(src, dst, i),
+            Equation::Subtract => s * self.source.weight(src, dst, i) -
+                                  d * self.destination.weight(src, dst, i),
+            Equation::ReverseSubtract => d * self.destination.weight(src, dst, i) -
+                                         s * self.source.weight(src, dst, i),
+            Equation::Min => s.min(d),
+            Equation::Max => s.max(d)
+        }
+    }
+}
+
+/// Fixed function blending, the colour and alpha channels
+/// can be blended independently.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Blend {
+    pub color: BlendChannel,
+    pub alpha: BlendChannel
+}
+
+impl Blend {
+    /// Use the same equation and factors for the colour and alpha channels
+    pub fn new(equation: Equation, source: BlendFactor, destination: BlendFactor) -> Blend {
+        let channel = BlendChannel {
+            equation: equation,
+            source: source,
+            destination: destination
+        };
+
+        Blend {
+            color: channel,
+            alpha: channel
+        }
+    }
+
+    /// Classic transparency, `src * src.a + dst * (1 - src.a)`
+    pub fn alpha() -> Blend {
+        Blend {
+            color: BlendChannel {
+                equation: Equation::Add,
+                source: BlendFactor::SrcAlpha,
+                destination: BlendFactor::OneMinusSrcAlpha
+            },
+            alpha: BlendChannel {
+                equation: Equation::Add,
+                source: BlendFactor::One,
+                destination: BlendFactor::OneMinusSrcAlpha
+            }
+        }
+    }
+
+    /// Transparency for colours that are already multiplied by their alpha
+    pub fn premultiplied() -> Blend {
+        Blend::new(Equation::Add, BlendFactor::One, BlendFactor::OneMinusSrcAlpha)
+    }
+
+    /// `src + dst`
+    pub fn additive() -> Blend {
+        Blend::new(Equation::Add, BlendFactor::One, BlendFactor::One)
+    }
+
+    /// Blend the source colour over the destination colour
+    #[inline]
+    pub fn apply<P: Blendable>(&self, src: P, dst: P) -> P {
+        let (s, d) = (src.to_rgba(), dst.to_rgba());
+        Blendable::from_rgba([self.color.apply(&s, &d, 0),
+                              self.color.apply(&s, &d, 1),
+                              self.color.apply(&s, &d, 2),
+                              self.alpha.apply(&s, &d, 3)])
+    }
+}
+
+/// A colour type that can be used with fixed function blending, the
+/// conversion is to and from normalized rgba.
+pub trait Blendable: Copy {
+    fn to_rgba(self) -> [f32; 4];
+    fn from_rgba(v: [f32; 4]) -> Self;
+}
+
+impl Blendable for [f32; 4] {
+    #[inline]
+    fn to_rgba(self) -> [f32; 4] { self }
+    #[inline]
+    fn from_rgba(v: [f32; 4]) -> [f32; 4] { v }
+}
+
+impl Blendable for Rgba<f32> {
+    #[inline]
+    fn to_rgba(self) -> [f32; 4] { self.0 }
+    #[inline]
+    fn from_rgba(v: [f32; 4]) -> Rgba<f32> { Rgba(v) }
+}
+
+impl Blendable for Rgba<u8> {
+    #[inline]
+    fn to_rgba(self) -> [f32; 4] {
+        let s = 1. / 255.;
+        [self.0[0] as f32 * s,
+         self.0[1] as f32 * s,
+         self.0[2] as f32 * s,
+         self.0[3] as f32 * s]
+    }
+
+    #[inline]
+    fn from_rgba(v: [f32; 4]) -> Rgba<u8> {
+        #[inline]
+        fn unorm(v: f32) -> u8 { (v.max(0.).min(1.) * 255. + 0.5) as u8 }
+        Rgba([unorm(v[0]), unorm(v[1]), unorm(v[2]), unorm(v[3])])
+    }
+}
+
+/// The colour of the pixels of a `Frame`. This is either a single
+/// `Blendable` colour, or a tuple of up to four of them for a frame with
+/// several colour targets. Each target is blended on its own.
+pub trait Planes: Copy {
+    /// Blend `new` over `dst`, target `i` is blended with `blend[i]`
+    /// or replaced if that is `None`.
+    fn blend_planes(dst: Self, new: Self, blend: &[Option<Blend>; 4]) -> Self;
+
+    /// Scale the alpha of every target by `a`
+    fn scale_alpha(self, a: f32) -> Self;
+
+    /// The average of the samples of a pixel
+    fn average(samples: &[Self]) -> Self;
+}
+
+impl<P: Blendable> Planes for P {
+    #[inline]
+    fn blend_planes(dst: P, new: P, blend: &[Option<Blend>; 4]) -> P {
+        match blend[0] {
+            Some(ref blend) => blend.apply(new, dst),
+            None => new
+        }
+    }
+
+    #[inline]
+    fn scale_alpha(self, a: f32) -> P {
+        let mut c = self.to_rgba();
+        c[3] *= a;
+        Blendable::from_rgba(c)
+    }
+
+    #[inline]
+    fn average(samples: &[P]) -> P {
+        average(samples.iter().cloned())
+    }
+}
+
+/// The average of the colours of `samples`, summed in a fixed accumulator
+#[inline]
+fn average<P: Blendable, I: Iterator<Item=P>>(samples: I) -> P {
+    let mut sum = [0.; 4];
+    let mut n = 0;
+    for s in samples {
+        let c = s.to_rgba();
+        for ch in 0..4 {
+            sum[ch] += c[ch];
+        }
+        n += 1;
+    }
+    let n = (n as f32).recip();
+    Blendable::from_rgba([sum[0] * n, sum[1] * n, sum[2] * n, sum[3] * n])
+}
+
+/// The operations of a `Planes` colour that fixed function blending and
+/// smooth lines need. They are kept as plain functions so that frames
+/// of any colour can be drawn to, the ones that can blend carry these.
+pub struct FixedFunction<P> {
+    pub blend: fn(P, P, &[Option<Blend>; 4]) -> P,
+    pub scale_alpha: fn(P, f32) -> P
+}
+
+impl<P> Clone for FixedFunction<P> {
+    fn clone(&self) -> FixedFunction<P> {
+        FixedFunction {
+            blend: self.blend,
+            scale_alpha: self.scale_alpha
+        }
+    }
+}
+
+impl<P> Copy for FixedFunction<P> {}
+
+impl<P: Planes> FixedFunction<P> {
+    pub fn new() -> FixedFunction<P> {
+        FixedFunction {
+            blend: Planes::blend_planes,
+            scale_alpha: Planes::scale_alpha
+        }
+    }
+}
+
+/// The blend state of target `i` on its own
+#[inline]
+fn target(blend: &[Option<Blend>; 4], i: usize) -> [Option<Blend>; 4] {
+    [blend[i], None, None, None]
+}
+
+impl<A: Blendable, B: Blendable> Planes for (A, B) {
+    #[inline]
+    fn blend_planes(dst: (A, B), new: (A, B), blend: &[Option<Blend>; 4]) -> (A, B) {
+        (Planes::blend_planes(dst.0, new.0, &target(blend, 0)),
+         Planes::blend_planes(dst.1, new.1, &target(blend, 1)))
+    }
+
+    #[inline]
+    fn scale_alpha(self, a: f32) -> (A, B) {
+        (self.0.scale_alpha(a), self.1.scale_alpha(a))
+    }
+
+    #[inline]
+    fn average(samples: &[(A, B)]) -> (A, B) {
+        (average(samples.iter().map(|s| s.0)),
+         average(samples.iter().map(|s| s.1)))
+    }
+}
+
+impl<A: Blendable, B: Blendable, C: Blendable> Planes for (A, B, C) {
+    #[inline]
+    fn blend_planes(dst: (A, B, C), new: (A, B, C), blend: &[Option<Blend>; 4]) -> (A, B, C) {
+        (Planes::blend_planes(dst.0, new.0, &target(blend, 0)),
+         Planes::blend_planes(dst.1, new.1, &target(blend, 1)),
+         Planes::blend_planes(dst.2, new.2, &target(blend, 2)))
+    }
+
+    #[inline]
+    fn scale_alpha(self, a: f32) -> (A, B, C) {
+        (self.0.scale_alpha(a), self.1.scale_alpha(a), self.2.scale_alpha(a))
+    }
+
+    #[inline]
+    fn average(samples: &[(A, B, C)]) -> (A, B, C) {
+        (average(samples.iter().map(|s| s.0)),
+         average(samples.iter().map(|s| s.1)),
+         average(samples.iter().map(|s| s.2)))
+    }
+}
+
+impl<A: Blendable, B: Blendable, C: Blendable, D: Blendable> Planes for (A, B, C, D) {
+    #[inline]
+    fn blend_planes(dst: (A, B, C, D), new: (A, B, C, D), blend: &[Option<Blend>; 4]) -> (A, B, C, D) {
+        (Planes::blend_planes(dst.0, new.0, &target(blend, 0)),
+         Planes::blend_planes(dst.1, new.1, &target(blend, 1)),
+         Planes::blend_planes(dst.2, new.2, &target(blend, 2)),
+         Planes::blend_planes(dst.3, new.3, &target(blend, 3)))
+    }
+
+    #[inline]
+    fn scale_alpha(self, a: f32) -> (A, B, C, D) {
+        (self.0.scale_alpha(a), self.1.scale_alpha(a), self.2.scale_alpha(a), self.3.scale_alpha(a))
+    }
+
+    #[inline]
+    fn average(samples: &[(A, B, C, D)]) -> (A, B, C, D) {
+        (average(samples.iter().map(|s| s.0)),
+         average(samples.iter().map(|s| s.1)),
+         average(samples.iter().map(|s| s.2)),
+         average(samples.iter().map(|s| s.3)))
+    }
+}
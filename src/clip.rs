@@ -0,0 +1,174 @@
+use cgmath::*;
+use genmesh::Triangle;
+
+use depth::DepthRange;
+
+/// A vertex produced by clipping. The position is in homogeneous
+/// clip space, the weight is the barycentric weight of this vertex
+/// relative to the unclipped source triangle.
+#[derive(Clone, Copy, Debug)]
+pub struct ClipVertex {
+    pub pos: Vector4<f32>,
+    pub weight: [f32; 3]
+}
+
+impl ClipVertex {
+    #[inline]
+    fn lerp(&self, other: &ClipVertex, t: f32) -> ClipVertex {
+        let s = 1. - t;
+        ClipVertex {
+            pos: self.pos.mul_s(s) + other.pos.mul_s(t),
+            weight: [self.weight[0] * s + other.weight[0] * t,
+                     self.weight[1] * s + other.weight[1] * t,
+                     self.weight[2] * s + other.weight[2] * t]
+        }
+    }
+}
+
+/// How far outside of the frame a triangle can reach in normalized device
+/// coordinates before it is clipped. Triangles are only clipped to the
+/// sides of this guard band, not the frame, which keeps the fixed-point
+/// edge functions of the rasterizer from overflowing.
+pub const GUARD_BAND: f32 = 16.;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Plane {
+    /// z >= -w
+    Near,
+    /// z >= 0, the near plane of `DepthRange::ZeroToOne`
+    NearZero,
+    /// z <= w
+    Far,
+    /// x >= -GUARD_BAND * w
+    Left,
+    /// x <= GUARD_BAND * w
+    Right,
+    /// y >= -GUARD_BAND * w
+    Bottom,
+    /// y <= GUARD_BAND * w
+    Top
+}
+
+impl Plane {
+    /// signed distance to the plane, positive values are inside
+    #[inline]
+    pub fn distance(self, v: &Vector4<f32>) -> f32 {
+        match self {
+            Plane::Near => v.w + v.z,
+            Plane::NearZero => v.z,
+            Plane::Far => v.w - v.z,
+            Plane::Left => GUARD_BAND * v.w + v.x,
+            Plane::Right => GUARD_BAND * v.w - v.x,
+            Plane::Bottom => GUARD_BAND * v.w + v.y,
+            Plane::Top => GUARD_BAND * v.w - v.y
+        }
+    }
+}
+
+/// Clipping a triangle against the near, far and the four guard band
+/// planes can produce at most nine vertices, one extra vertex per plane.
+const MAX_VERTICES: usize = 9;
+
+/// A convex polygon in clip space, the result of clipping a triangle
+#[derive(Clone, Copy)]
+pub struct Clipped {
+    vertices: [ClipVertex; MAX_VERTICES],
+    len: usize,
+    next: usize
+}
+
+impl Clipped {
+    fn empty(v: ClipVertex) -> Clipped {
+        Clipped {
+            vertices: [v; MAX_VERTICES],
+            len: 0,
+            next: 1
+        }
+    }
+
+    fn clip(&self, plane: Plane) -> Clipped {
+        let mut out = Clipped::empty(self.vertices[0]);
+
+        for i in 0..self.len {
+            let a = &self.vertices[i];
+            let b = &self.vertices[(i + 1) % self.len];
+            let da = plane.distance(&a.pos);
+            let db = plane.distance(&b.pos);
+
+            if da >= 0. {
+                out.push(*a);
+            }
+            if (da >= 0.) != (db >= 0.) {
+                out.push(a.lerp(b, da / (da - db)));
+            }
+        }
+        out
+    }
+
+    #[inline]
+    fn push(&mut self, v: ClipVertex) {
+        self.vertices[self.len] = v;
+        self.len += 1;
+    }
+
+    /// the number of vertices in the polygon
+    #[inline]
+    pub fn len(&self) -> usize { self.len }
+}
+
+/// The clipped polygon is emitted as a triangle fan
+impl Iterator for Clipped {
+    type Item = Triangle<ClipVertex>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Triangle<ClipVertex>> {
+        if self.next + 1 >= self.len {
+            return None;
+        }
+
+        let i = self.next;
+        self.next += 1;
+        Some(Triangle::new(self.vertices[0],
+                           self.vertices[i],
+                           self.vertices[i+1]))
+    }
+}
+
+/// Clip a triangle against the near and far planes and the guard band.
+/// The input must be in homogeneous clip space (before the perspective divide).
+pub fn clip_triangle(t: Triangle<Vector4<f32>>) -> Clipped {
+    clip_triangle_range(t, DepthRange::NegativeOneToOne)
+}
+
+/// Clip a triangle with the near plane of the depth `range`
+pub fn clip_triangle_range(t: Triangle<Vector4<f32>>, range: DepthRange) -> Clipped {
+    let near = match range {
+        DepthRange::NegativeOneToOne => Plane::Near,
+        DepthRange::ZeroToOne => Plane::NearZero
+    };
+
+    let mut poly = Clipped::empty(ClipVertex{pos: t.x, weight: [1., 0., 0.]});
+    poly.push(ClipVertex{pos: t.x, weight: [1., 0., 0.]});
+    poly.push(ClipVertex{pos: t.y, weight: [0., 1., 0.]});
+    poly.push(ClipVertex{pos: t.z, weight: [0., 0., 1.]});
+
+    let planes = [near, Plane::Far, Plane::Left, Plane::Right, Plane::Bottom, Plane::Top];
+    for &plane in planes.iter() {
+        let (dx, dy, dz) = (plane.distance(&t.x),
+                            plane.distance(&t.y),
+                            plane.distance(&t.z));
+
+        // fully outside of this plane, nothing is left to draw
+        if dx < 0. && dy < 0. && dz < 0. {
+            poly.len = 0;
+            return poly;
+        }
+
+        // partially outside, the polygon needs to be split
+        if dx < 0. || dy < 0. || dz < 0. {
+            poly = poly.clip(plane);
+        }
+    }
+
+    poly
+}
@@ -0,0 +1,216 @@
+use cgmath::*;
+
+use f32x8::f32x8x8;
+use tile::Put;
+
+/// How the depth buffer of a `Frame` stores the window depth of each
+/// sample, from 0 at the near plane to 1 at the far plane. The incoming
+/// depth is converted to the stored format before it is compared.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthFormat {
+    /// 16 bit unsigned normalized depth
+    Unorm16,
+    /// 24 bit unsigned normalized depth, the stencil is packed into
+    /// the same 32 bit word
+    Unorm24,
+    /// 32 bit floating point depth
+    Float32
+}
+
+/// The range of depth in normalized device coordinates, geometry
+/// outside of it is clipped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthRange {
+    /// `-w <= z <= w`, the OpenGL convention
+    NegativeOneToOne,
+    /// `0 <= z <= w`, use this with `reversed_z` for the most precision
+    ZeroToOne
+}
+
+impl DepthRange {
+    /// The depth of the near plane
+    #[inline]
+    pub fn near(self) -> f32 {
+        match self {
+            DepthRange::NegativeOneToOne => -1.,
+            DepthRange::ZeroToOne => 0.
+        }
+    }
+
+    /// signed distance to the near plane in clip space, positive values are inside
+    #[inline]
+    pub fn near_distance(self, v: &Vector4<f32>) -> f32 {
+        match self {
+            DepthRange::NegativeOneToOne => v.w + v.z,
+            DepthRange::ZeroToOne => v.z
+        }
+    }
+
+    /// The window depth of the normalized device depth `z`
+    #[inline]
+    pub fn window(self, z: f32) -> f32 {
+        let near = self.near();
+        (z - near) / (1. - near)
+    }
+
+    /// The window depth of every pixel in a tile
+    #[inline]
+    pub fn window_tile(self, z: f32x8x8) -> f32x8x8 {
+        match self {
+            DepthRange::NegativeOneToOne => (z + f32x8x8::broadcast(1.)) * 0.5,
+            DepthRange::ZeroToOne => z
+        }
+    }
+
+    /// The normalized device depth of the window depth `w`
+    #[inline]
+    pub fn ndc(self, w: f32) -> f32 {
+        let near = self.near();
+        w * (1. - near) + near
+    }
+}
+
+impl DepthFormat {
+    /// The stored value of the window depth 1
+    #[inline]
+    fn max(self) -> f32 {
+        match self {
+            DepthFormat::Unorm16 => 65535.,
+            DepthFormat::Unorm24 => 16777215.,
+            DepthFormat::Float32 => 1.
+        }
+    }
+
+    /// The unsigned normalized value the window depth `w` is stored
+    /// as, `w` is clamped to 0 to 1.
+    #[inline]
+    pub fn encode(self, w: f32) -> u32 {
+        (w.max(0.).min(1.) * self.max()).round() as u32
+    }
+
+    /// The window depth of a stored unsigned normalized value
+    #[inline]
+    pub fn decode(self, d: u32) -> f32 {
+        d as f32 / self.max()
+    }
+
+    /// Round the normalized device depth `z` to what the format
+    /// stores of it when drawn with `range`
+    #[inline]
+    pub fn quantize(self, z: f32, range: DepthRange) -> f32 {
+        if self == DepthFormat::Float32 {
+            return z;
+        }
+        range.ndc(self.decode(self.encode(range.window(z))))
+    }
+}
+
+/// Turn an OpenGL style projection matrix into one for reversed-Z, where
+/// the near plane is at depth 1 and the far plane at depth 0. Draw with
+/// `RasterState::reversed_z` to a frame with `FrameFormat::reversed_z`.
+///
+/// Floating point depth is most precise close to 0, reversing it spends
+/// that precision on the far away geometry that needs it the most.
+pub fn reversed_z(proj: Matrix4<f32>) -> Matrix4<f32> {
+    // z' = (w - z) / 2 maps -w to w and w to 0
+    let mut m = proj;
+    m.x.z = (proj.x.w - proj.x.z) * 0.5;
+    m.y.z = (proj.y.w - proj.y.z) * 0.5;
+    m.z.z = (proj.z.w - proj.z.z) * 0.5;
+    m.w.z = (proj.w.w - proj.w.z) * 0.5;
+    m
+}
+
+/// How `Frame::depth_to_vec` and `Frame::depth_to_image` convert the
+/// depth that is stored in the frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthOutput {
+    /// window space depth from 0 to 1, what reading back the depth
+    /// buffer in OpenGL or Direct3D with the same `DepthRange` returns
+    Window(DepthRange),
+    /// the distance from the eye, for an OpenGL style projection
+    /// matrix that maps the `near` and `far` planes to -1 and 1
+    Linear { near: f32, far: f32 },
+    /// the distance from the eye, for a Direct3D style projection matrix
+    /// that maps the `near` and `far` planes to 0 and 1, drawn with
+    /// `DepthRange::ZeroToOne`
+    LinearZeroToOne { near: f32, far: f32 },
+    /// the distance from the eye, for a projection matrix that was
+    /// passed through `reversed_z`
+    LinearReversed { near: f32, far: f32 }
+}
+
+impl DepthOutput {
+    /// The depth range that stored window depth is read back with
+    #[inline]
+    pub fn range(self) -> DepthRange {
+        match self {
+            DepthOutput::Window(range) => range,
+            DepthOutput::Linear { .. } => DepthRange::NegativeOneToOne,
+            DepthOutput::LinearZeroToOne { .. } |
+            DepthOutput::LinearReversed { .. } => DepthRange::ZeroToOne
+        }
+    }
+
+    /// Convert a depth in normalized device coordinates
+    #[inline]
+    pub fn convert(self, z: f32) -> f32 {
+        match self {
+            DepthOutput::Window(range) => {
+                let near = range.near();
+                (z - near) / (1. - near)
+            }
+            DepthOutput::Linear { near, far } => {
+                2. * near * far / (far + near - z * (far - near))
+            }
+            DepthOutput::LinearZeroToOne { near, far } => {
+                near * far / (far - z * (far - near))
+            }
+            DepthOutput::LinearReversed { near, far } => {
+                near * far / (near + z * (far - near))
+            }
+        }
+    }
+
+    /// Convert a depth in normalized device coordinates into 0 to 1,
+    /// linear depth is 0 at the near plane and 1 at the far plane.
+    #[inline]
+    pub fn normalize(self, z: f32) -> f32 {
+        let d = match self {
+            DepthOutput::Window(_) => self.convert(z),
+            DepthOutput::Linear { near, far } |
+            DepthOutput::LinearZeroToOne { near, far } |
+            DepthOutput::LinearReversed { near, far } => {
+                (self.convert(z) - near) / (far - near)
+            }
+        };
+        d.max(0.).min(1.)
+    }
+}
+
+/// The window depth of every pixel of a frame, top row first
+pub struct DepthBuffer {
+    width: u32,
+    height: u32,
+    pub data: Vec<f32>
+}
+
+impl DepthBuffer {
+    pub fn new(width: u32, height: u32) -> DepthBuffer {
+        DepthBuffer {
+            width: width,
+            height: height,
+            data: vec![0.; (width * height) as usize]
+        }
+    }
+}
+
+impl Put<f32> for DepthBuffer {
+    fn put(&mut self, x: u32, y: u32, z: f32) {
+        // partial tiles on the edge of the frame extend past the buffer
+        if x < self.width && y < self.height {
+            let row = self.height - 1 - y;
+            self.data[(row * self.width + x) as usize] = z;
+        }
+    }
+}
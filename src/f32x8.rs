@@ -104,6 +104,17 @@ impl f32x8x8 {
         self.7.replace(other.7, (mask >> 56) as u8);
     }
 
+    /// The lanes of every row, pixel `i` is at `x = i & 7`, `y = i >> 3`
+    #[inline]
+    pub fn to_array(self) -> [f32; 64] {
+        unsafe { mem::transmute(self) }
+    }
+
+    #[inline]
+    pub fn from_array(v: [f32; 64]) -> f32x8x8 {
+        unsafe { mem::transmute(v) }
+    }
+
     /// casts a each f32 to its bit forms as u32
     /// this is numerically useless, but used for bit twiddling
     /// inside of the library
@@ -242,6 +253,18 @@ impl Mul<f32> for f32x8x8 {
     }
 }
 
+impl Div<f32x8x8> for f32x8x8 {
+    type Output = f32x8x8;
+
+    #[inline]
+    fn div(self, rhs: f32x8x8) -> f32x8x8 {
+        f32x8x8(self.0 / rhs.0, self.1 / rhs.1,
+                self.2 / rhs.2, self.3 / rhs.3,
+                self.4 / rhs.4, self.5 / rhs.5,
+                self.6 / rhs.6, self.7 / rhs.7)
+    }
+}
+
 impl Neg for f32x8x8 {
     type Output = f32x8x8;
 
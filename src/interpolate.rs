@@ -1,6 +1,8 @@
 pub use cgmath::*;
 use genmesh::Triangle;
 
+use f32x8::f32x8x8;
+
 
 #[derive(Clone, Debug, Copy)]
 pub struct Flat<T>(pub T);
@@ -8,170 +10,519 @@ pub struct Flat<T>(pub T);
 impl<T: Clone> Interpolate for Flat<T> {
     type Out = T;
     #[inline]
-    fn interpolate(src: &Triangle<Flat<T>>, _: [f32; 3]) -> T { src.x.0.clone() }
+    fn interpolate(src: Triangle<&Flat<T>>, _: [f32; 3]) -> T { src.x.0.clone() }
 }
 
+/// An attribute that is interpolated linearly in screen space
+/// rather than being perspective corrected.
+#[derive(Clone, Debug, Copy)]
+pub struct NoPerspective<T>(pub T);
+
+impl<T, O> Interpolate for NoPerspective<T>
+    where T: Interpolate<Out=O> {
+    type Out = O;
+    #[inline]
+    fn interpolate(src: Triangle<&NoPerspective<T>>, w: [f32; 3]) -> O {
+        Interpolate::interpolate(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w)
+    }
+
+    #[inline]
+    fn interpolate_screen(src: Triangle<&NoPerspective<T>>, _: [f32; 3], screen: [f32; 3]) -> O {
+        Interpolate::interpolate(Triangle::new(&src.x.0, &src.y.0, &src.z.0), screen)
+    }
+}
+
+/// An attribute that is interpolated across a triangle. The vertices
+/// are borrowed, so attributes that are shared by many triangles are
+/// never copied.
 pub trait Interpolate {
     type Out;
 
     #[inline]
-    fn interpolate(src: &Triangle<Self>, w: [f32; 3]) -> Self::Out;
+    fn interpolate(src: Triangle<&Self>, w: [f32; 3]) -> Self::Out;
+
+    /// Interpolate with both the perspective correct weights `w` and
+    /// the screen space weights `screen`. Only attributes that are
+    /// not perspective corrected care about the latter.
+    #[inline]
+    fn interpolate_screen(src: Triangle<&Self>, w: [f32; 3], _: [f32; 3]) -> Self::Out {
+        Interpolate::interpolate(src, w)
+    }
 }
 
 impl Interpolate for f32 {
     type Out = f32;
     #[inline]
-    fn interpolate(src: &Triangle<f32>, w: [f32; 3]) -> f32 {
-        src.x * w[0] + src.y * w[1] + src.z * w[2]
+    fn interpolate(src: Triangle<&f32>, w: [f32; 3]) -> f32 {
+        *src.x * w[0] + *src.y * w[1] + *src.z * w[2]
     }
 }
 
 impl Interpolate for [f32; 2] {
     type Out = [f32; 2];
     #[inline]
-    fn interpolate(src: &Triangle<[f32; 2]>, w: [f32; 3]) -> [f32; 2] {
-        [Interpolate::interpolate(&Triangle::new(src.x[0], src.y[0], src.z[0]), w),
-         Interpolate::interpolate(&Triangle::new(src.x[1], src.y[1], src.z[1]), w)]
+    fn interpolate(src: Triangle<&[f32; 2]>, w: [f32; 3]) -> [f32; 2] {
+        [Interpolate::interpolate(Triangle::new(&src.x[0], &src.y[0], &src.z[0]), w),
+         Interpolate::interpolate(Triangle::new(&src.x[1], &src.y[1], &src.z[1]), w)]
     }
 }
 
 impl Interpolate for [f32; 3] {
     type Out = [f32; 3];
     #[inline]
-    fn interpolate(src: &Triangle<[f32; 3]>, w: [f32; 3]) -> [f32; 3] {
-        [Interpolate::interpolate(&Triangle::new(src.x[0], src.y[0], src.z[0]), w),
-         Interpolate::interpolate(&Triangle::new(src.x[1], src.y[1], src.z[1]), w),
-         Interpolate::interpolate(&Triangle::new(src.x[2], src.y[2], src.z[2]), w)]
+    fn interpolate(src: Triangle<&[f32; 3]>, w: [f32; 3]) -> [f32; 3] {
+        [Interpolate::interpolate(Triangle::new(&src.x[0], &src.y[0], &src.z[0]), w),
+         Interpolate::interpolate(Triangle::new(&src.x[1], &src.y[1], &src.z[1]), w),
+         Interpolate::interpolate(Triangle::new(&src.x[2], &src.y[2], &src.z[2]), w)]
     }
 }
 
 impl Interpolate for [f32; 4] {
     type Out = [f32; 4];
     #[inline]
-    fn interpolate(src: &Triangle<[f32; 4]>, w: [f32; 3]) -> [f32; 4] {
-        [Interpolate::interpolate(&Triangle::new(src.x[0], src.y[0], src.z[0]), w),
-         Interpolate::interpolate(&Triangle::new(src.x[1], src.y[1], src.z[1]), w),
-         Interpolate::interpolate(&Triangle::new(src.x[2], src.y[2], src.z[2]), w),
-         Interpolate::interpolate(&Triangle::new(src.x[3], src.y[3], src.z[3]), w)]
+    fn interpolate(src: Triangle<&[f32; 4]>, w: [f32; 3]) -> [f32; 4] {
+        [Interpolate::interpolate(Triangle::new(&src.x[0], &src.y[0], &src.z[0]), w),
+         Interpolate::interpolate(Triangle::new(&src.x[1], &src.y[1], &src.z[1]), w),
+         Interpolate::interpolate(Triangle::new(&src.x[2], &src.y[2], &src.z[2]), w),
+         Interpolate::interpolate(Triangle::new(&src.x[3], &src.y[3], &src.z[3]), w)]
     }
 }
 
 impl<A, B, AO, BO> Interpolate for (A, B)
-    where A: Interpolate<Out=AO> + Clone,
-          B: Interpolate<Out=BO> + Clone {
+    where A: Interpolate<Out=AO>,
+          B: Interpolate<Out=BO> {
     type Out = (AO, BO);
     #[inline]
-    fn interpolate(src: &Triangle<(A, B)>, w: [f32; 3]) -> (AO, BO) {
-        (Interpolate::interpolate(&Triangle::new(src.x.0.clone(), src.y.0.clone(), src.z.0.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.1.clone(), src.y.1.clone(), src.z.1.clone()), w))
+    fn interpolate(src: Triangle<&(A, B)>, w: [f32; 3]) -> (AO, BO) {
+        (Interpolate::interpolate(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         Interpolate::interpolate(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w))
+    }
+
+    #[inline]
+    fn interpolate_screen(src: Triangle<&(A, B)>, w: [f32; 3], screen: [f32; 3]) -> (AO, BO) {
+        (Interpolate::interpolate_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen))
     }
 }
 
 impl<A, B, C, AO, BO, CO> Interpolate for (A, B, C)
-    where A: Interpolate<Out=AO> + Clone,
-          B: Interpolate<Out=BO> + Clone,
-          C: Interpolate<Out=CO> + Clone {
+    where A: Interpolate<Out=AO>,
+          B: Interpolate<Out=BO>,
+          C: Interpolate<Out=CO> {
     type Out = (AO, BO, CO);
     #[inline]
-    fn interpolate(src: &Triangle<(A, B, C)>, w: [f32; 3]) -> (AO, BO, CO) {
-        (Interpolate::interpolate(&Triangle::new(src.x.0.clone(), src.y.0.clone(), src.z.0.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.1.clone(), src.y.1.clone(), src.z.1.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.2.clone(), src.y.2.clone(), src.z.2.clone()), w))
+    fn interpolate(src: Triangle<&(A, B, C)>, w: [f32; 3]) -> (AO, BO, CO) {
+        (Interpolate::interpolate(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         Interpolate::interpolate(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w),
+         Interpolate::interpolate(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w))
+    }
+
+    #[inline]
+    fn interpolate_screen(src: Triangle<&(A, B, C)>, w: [f32; 3], screen: [f32; 3]) -> (AO, BO, CO) {
+        (Interpolate::interpolate_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w, screen))
     }
 }
 
 impl<A, B, C, D, AO, BO, CO, DO> Interpolate for (A, B, C, D)
-    where A: Interpolate<Out=AO> + Clone,
-          B: Interpolate<Out=BO> + Clone,
-          C: Interpolate<Out=CO> + Clone,
-          D: Interpolate<Out=DO> + Clone {
+    where A: Interpolate<Out=AO>,
+          B: Interpolate<Out=BO>,
+          C: Interpolate<Out=CO>,
+          D: Interpolate<Out=DO> {
     type Out = (AO, BO, CO, DO);
     #[inline]
-    fn interpolate(src: &Triangle<(A, B, C, D)>, w: [f32; 3]) -> (AO, BO, CO, DO) {
-        (Interpolate::interpolate(&Triangle::new(src.x.0.clone(), src.y.0.clone(), src.z.0.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.1.clone(), src.y.1.clone(), src.z.1.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.2.clone(), src.y.2.clone(), src.z.2.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.3.clone(), src.y.3.clone(), src.z.3.clone()), w))
+    fn interpolate(src: Triangle<&(A, B, C, D)>, w: [f32; 3]) -> (AO, BO, CO, DO) {
+        (Interpolate::interpolate(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         Interpolate::interpolate(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w),
+         Interpolate::interpolate(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w),
+         Interpolate::interpolate(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w))
+    }
+
+    #[inline]
+    fn interpolate_screen(src: Triangle<&(A, B, C, D)>, w: [f32; 3], screen: [f32; 3]) -> (AO, BO, CO, DO) {
+        (Interpolate::interpolate_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w, screen))
     }
 }
 
 impl<A, B, C, D, E, AO, BO, CO, DO, EO> Interpolate for (A, B, C, D, E)
-    where A: Interpolate<Out=AO> + Clone,
-          B: Interpolate<Out=BO> + Clone,
-          C: Interpolate<Out=CO> + Clone,
-          D: Interpolate<Out=DO> + Clone,
-          E: Interpolate<Out=EO> + Clone {
+    where A: Interpolate<Out=AO>,
+          B: Interpolate<Out=BO>,
+          C: Interpolate<Out=CO>,
+          D: Interpolate<Out=DO>,
+          E: Interpolate<Out=EO> {
     type Out = (AO, BO, CO, DO, EO);
     #[inline]
-    fn interpolate(src: &Triangle<(A, B, C, D, E)>, w: [f32; 3]) -> (AO, BO, CO, DO, EO) {
-        (Interpolate::interpolate(&Triangle::new(src.x.0.clone(), src.y.0.clone(), src.z.0.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.1.clone(), src.y.1.clone(), src.z.1.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.2.clone(), src.y.2.clone(), src.z.2.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.3.clone(), src.y.3.clone(), src.z.3.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.4.clone(), src.y.4.clone(), src.z.4.clone()), w))
+    fn interpolate(src: Triangle<&(A, B, C, D, E)>, w: [f32; 3]) -> (AO, BO, CO, DO, EO) {
+        (Interpolate::interpolate(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         Interpolate::interpolate(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w),
+         Interpolate::interpolate(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w),
+         Interpolate::interpolate(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w),
+         Interpolate::interpolate(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w))
+    }
+
+    #[inline]
+    fn interpolate_screen(src: Triangle<&(A, B, C, D, E)>, w: [f32; 3], screen: [f32; 3]) -> (AO, BO, CO, DO, EO) {
+        (Interpolate::interpolate_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w, screen))
     }
 }
 
 impl<A, B, C, D, E, F, AO, BO, CO, DO, EO, FO> Interpolate for (A, B, C, D, E, F)
-    where A: Interpolate<Out=AO> + Clone,
-          B: Interpolate<Out=BO> + Clone,
-          C: Interpolate<Out=CO> + Clone,
-          D: Interpolate<Out=DO> + Clone,
-          E: Interpolate<Out=EO> + Clone,
-          F: Interpolate<Out=FO> + Clone {
+    where A: Interpolate<Out=AO>,
+          B: Interpolate<Out=BO>,
+          C: Interpolate<Out=CO>,
+          D: Interpolate<Out=DO>,
+          E: Interpolate<Out=EO>,
+          F: Interpolate<Out=FO> {
     type Out = (AO, BO, CO, DO, EO, FO);
     #[inline]
-    fn interpolate(src: &Triangle<(A, B, C, D, E, F)>, w: [f32; 3]) -> (AO, BO, CO, DO, EO, FO) {
-        (Interpolate::interpolate(&Triangle::new(src.x.0.clone(), src.y.0.clone(), src.z.0.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.1.clone(), src.y.1.clone(), src.z.1.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.2.clone(), src.y.2.clone(), src.z.2.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.3.clone(), src.y.3.clone(), src.z.3.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.4.clone(), src.y.4.clone(), src.z.4.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.5.clone(), src.y.5.clone(), src.z.5.clone()), w))
+    fn interpolate(src: Triangle<&(A, B, C, D, E, F)>, w: [f32; 3]) -> (AO, BO, CO, DO, EO, FO) {
+        (Interpolate::interpolate(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         Interpolate::interpolate(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w),
+         Interpolate::interpolate(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w),
+         Interpolate::interpolate(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w),
+         Interpolate::interpolate(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w),
+         Interpolate::interpolate(Triangle::new(&src.x.5, &src.y.5, &src.z.5), w))
+    }
+
+    #[inline]
+    fn interpolate_screen(src: Triangle<&(A, B, C, D, E, F)>, w: [f32; 3], screen: [f32; 3]) -> (AO, BO, CO, DO, EO, FO) {
+        (Interpolate::interpolate_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.5, &src.y.5, &src.z.5), w, screen))
     }
 }
 
 impl<A, B, C, D, E, F, G, AO, BO, CO, DO, EO, FO, GO> Interpolate for (A, B, C, D, E, F, G)
-    where A: Interpolate<Out=AO> + Clone,
-          B: Interpolate<Out=BO> + Clone,
-          C: Interpolate<Out=CO> + Clone,
-          D: Interpolate<Out=DO> + Clone,
-          E: Interpolate<Out=EO> + Clone,
-          F: Interpolate<Out=FO> + Clone,
-          G: Interpolate<Out=GO> + Clone {
+    where A: Interpolate<Out=AO>,
+          B: Interpolate<Out=BO>,
+          C: Interpolate<Out=CO>,
+          D: Interpolate<Out=DO>,
+          E: Interpolate<Out=EO>,
+          F: Interpolate<Out=FO>,
+          G: Interpolate<Out=GO> {
     type Out = (AO, BO, CO, DO, EO, FO, GO);
     #[inline]
-    fn interpolate(src: &Triangle<(A, B, C, D, E, F, G)>, w: [f32; 3]) -> (AO, BO, CO, DO, EO, FO, GO) {
-        (Interpolate::interpolate(&Triangle::new(src.x.0.clone(), src.y.0.clone(), src.z.0.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.1.clone(), src.y.1.clone(), src.z.1.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.2.clone(), src.y.2.clone(), src.z.2.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.3.clone(), src.y.3.clone(), src.z.3.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.4.clone(), src.y.4.clone(), src.z.4.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.5.clone(), src.y.5.clone(), src.z.5.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.6.clone(), src.y.6.clone(), src.z.6.clone()), w))
+    fn interpolate(src: Triangle<&(A, B, C, D, E, F, G)>, w: [f32; 3]) -> (AO, BO, CO, DO, EO, FO, GO) {
+        (Interpolate::interpolate(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         Interpolate::interpolate(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w),
+         Interpolate::interpolate(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w),
+         Interpolate::interpolate(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w),
+         Interpolate::interpolate(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w),
+         Interpolate::interpolate(Triangle::new(&src.x.5, &src.y.5, &src.z.5), w),
+         Interpolate::interpolate(Triangle::new(&src.x.6, &src.y.6, &src.z.6), w))
+    }
+
+    #[inline]
+    fn interpolate_screen(src: Triangle<&(A, B, C, D, E, F, G)>, w: [f32; 3], screen: [f32; 3]) -> (AO, BO, CO, DO, EO, FO, GO) {
+        (Interpolate::interpolate_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.5, &src.y.5, &src.z.5), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.6, &src.y.6, &src.z.6), w, screen))
     }
 }
 
 impl<A, B, C, D, E, F, G, H, AO, BO, CO, DO, EO, FO, GO, HO> Interpolate for (A, B, C, D, E, F, G, H)
-    where A: Interpolate<Out=AO> + Clone,
-          B: Interpolate<Out=BO> + Clone,
-          C: Interpolate<Out=CO> + Clone,
-          D: Interpolate<Out=DO> + Clone,
-          E: Interpolate<Out=EO> + Clone,
-          F: Interpolate<Out=FO> + Clone,
-          G: Interpolate<Out=GO> + Clone,
-          H: Interpolate<Out=HO> + Clone {
+    where A: Interpolate<Out=AO>,
+          B: Interpolate<Out=BO>,
+          C: Interpolate<Out=CO>,
+          D: Interpolate<Out=DO>,
+          E: Interpolate<Out=EO>,
+          F: Interpolate<Out=FO>,
+          G: Interpolate<Out=GO>,
+          H: Interpolate<Out=HO> {
     type Out = (AO, BO, CO, DO, EO, FO, GO, HO);
     #[inline]
-    fn interpolate(src: &Triangle<(A, B, C, D, E, F, G, H)>, w: [f32; 3]) -> (AO, BO, CO, DO, EO, FO, GO, HO) {
-        (Interpolate::interpolate(&Triangle::new(src.x.0.clone(), src.y.0.clone(), src.z.0.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.1.clone(), src.y.1.clone(), src.z.1.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.2.clone(), src.y.2.clone(), src.z.2.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.3.clone(), src.y.3.clone(), src.z.3.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.4.clone(), src.y.4.clone(), src.z.4.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.5.clone(), src.y.5.clone(), src.z.5.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.6.clone(), src.y.6.clone(), src.z.6.clone()), w),
-         Interpolate::interpolate(&Triangle::new(src.x.7.clone(), src.y.7.clone(), src.z.7.clone()), w))
+    fn interpolate(src: Triangle<&(A, B, C, D, E, F, G, H)>, w: [f32; 3]) -> (AO, BO, CO, DO, EO, FO, GO, HO) {
+        (Interpolate::interpolate(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         Interpolate::interpolate(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w),
+         Interpolate::interpolate(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w),
+         Interpolate::interpolate(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w),
+         Interpolate::interpolate(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w),
+         Interpolate::interpolate(Triangle::new(&src.x.5, &src.y.5, &src.z.5), w),
+         Interpolate::interpolate(Triangle::new(&src.x.6, &src.y.6, &src.z.6), w),
+         Interpolate::interpolate(Triangle::new(&src.x.7, &src.y.7, &src.z.7), w))
+    }
+
+    #[inline]
+    fn interpolate_screen(src: Triangle<&(A, B, C, D, E, F, G, H)>, w: [f32; 3], screen: [f32; 3]) -> (AO, BO, CO, DO, EO, FO, GO, HO) {
+        (Interpolate::interpolate_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.5, &src.y.5, &src.z.5), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.6, &src.y.6, &src.z.6), w, screen),
+         Interpolate::interpolate_screen(Triangle::new(&src.x.7, &src.y.7, &src.z.7), w, screen))
+    }
+}
+
+
+/// Interpolate an attribute for every pixel of an 8x8 tile at once,
+/// this is used by `WideFragment`. Every `f32` of the attribute
+/// becomes an `f32x8x8` with one lane per pixel.
+pub trait InterpolateWide {
+    type Wide;
+
+    #[inline]
+    fn interpolate_wide(src: Triangle<&Self>, w: [f32x8x8; 3]) -> Self::Wide;
+
+    /// See `Interpolate::interpolate_screen`
+    #[inline]
+    fn interpolate_wide_screen(src: Triangle<&Self>, w: [f32x8x8; 3], _: [f32x8x8; 3]) -> Self::Wide {
+        InterpolateWide::interpolate_wide(src, w)
+    }
+}
+
+impl<T: Clone> InterpolateWide for Flat<T> {
+    type Wide = T;
+    #[inline]
+    fn interpolate_wide(src: Triangle<&Flat<T>>, _: [f32x8x8; 3]) -> T { src.x.0.clone() }
+}
+
+impl<T, O> InterpolateWide for NoPerspective<T>
+    where T: InterpolateWide<Wide=O> {
+    type Wide = O;
+    #[inline]
+    fn interpolate_wide(src: Triangle<&NoPerspective<T>>, w: [f32x8x8; 3]) -> O {
+        InterpolateWide::interpolate_wide(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w)
+    }
+
+    #[inline]
+    fn interpolate_wide_screen(src: Triangle<&NoPerspective<T>>, _: [f32x8x8; 3], screen: [f32x8x8; 3]) -> O {
+        InterpolateWide::interpolate_wide(Triangle::new(&src.x.0, &src.y.0, &src.z.0), screen)
+    }
+}
+
+impl InterpolateWide for f32 {
+    type Wide = f32x8x8;
+    #[inline]
+    fn interpolate_wide(src: Triangle<&f32>, w: [f32x8x8; 3]) -> f32x8x8 {
+        w[0] * *src.x + w[1] * *src.y + w[2] * *src.z
+    }
+}
+
+impl InterpolateWide for [f32; 2] {
+    type Wide = [f32x8x8; 2];
+    #[inline]
+    fn interpolate_wide(src: Triangle<&[f32; 2]>, w: [f32x8x8; 3]) -> [f32x8x8; 2] {
+        [InterpolateWide::interpolate_wide(Triangle::new(&src.x[0], &src.y[0], &src.z[0]), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x[1], &src.y[1], &src.z[1]), w)]
+    }
+}
+
+impl InterpolateWide for [f32; 3] {
+    type Wide = [f32x8x8; 3];
+    #[inline]
+    fn interpolate_wide(src: Triangle<&[f32; 3]>, w: [f32x8x8; 3]) -> [f32x8x8; 3] {
+        [InterpolateWide::interpolate_wide(Triangle::new(&src.x[0], &src.y[0], &src.z[0]), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x[1], &src.y[1], &src.z[1]), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x[2], &src.y[2], &src.z[2]), w)]
+    }
+}
+
+impl InterpolateWide for [f32; 4] {
+    type Wide = [f32x8x8; 4];
+    #[inline]
+    fn interpolate_wide(src: Triangle<&[f32; 4]>, w: [f32x8x8; 3]) -> [f32x8x8; 4] {
+        [InterpolateWide::interpolate_wide(Triangle::new(&src.x[0], &src.y[0], &src.z[0]), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x[1], &src.y[1], &src.z[1]), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x[2], &src.y[2], &src.z[2]), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x[3], &src.y[3], &src.z[3]), w)]
+    }
+}
+
+impl<A, B, AO, BO> InterpolateWide for (A, B)
+    where A: InterpolateWide<Wide=AO>,
+          B: InterpolateWide<Wide=BO> {
+    type Wide = (AO, BO);
+    #[inline]
+    fn interpolate_wide(src: Triangle<&(A, B)>, w: [f32x8x8; 3]) -> (AO, BO) {
+        (InterpolateWide::interpolate_wide(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w))
+    }
+
+    #[inline]
+    fn interpolate_wide_screen(src: Triangle<&(A, B)>, w: [f32x8x8; 3], screen: [f32x8x8; 3]) -> (AO, BO) {
+        (InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen))
+    }
+}
+
+impl<A, B, C, AO, BO, CO> InterpolateWide for (A, B, C)
+    where A: InterpolateWide<Wide=AO>,
+          B: InterpolateWide<Wide=BO>,
+          C: InterpolateWide<Wide=CO> {
+    type Wide = (AO, BO, CO);
+    #[inline]
+    fn interpolate_wide(src: Triangle<&(A, B, C)>, w: [f32x8x8; 3]) -> (AO, BO, CO) {
+        (InterpolateWide::interpolate_wide(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w))
+    }
+
+    #[inline]
+    fn interpolate_wide_screen(src: Triangle<&(A, B, C)>, w: [f32x8x8; 3], screen: [f32x8x8; 3]) -> (AO, BO, CO) {
+        (InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w, screen))
+    }
+}
+
+impl<A, B, C, D, AO, BO, CO, DO> InterpolateWide for (A, B, C, D)
+    where A: InterpolateWide<Wide=AO>,
+          B: InterpolateWide<Wide=BO>,
+          C: InterpolateWide<Wide=CO>,
+          D: InterpolateWide<Wide=DO> {
+    type Wide = (AO, BO, CO, DO);
+    #[inline]
+    fn interpolate_wide(src: Triangle<&(A, B, C, D)>, w: [f32x8x8; 3]) -> (AO, BO, CO, DO) {
+        (InterpolateWide::interpolate_wide(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w))
+    }
+
+    #[inline]
+    fn interpolate_wide_screen(src: Triangle<&(A, B, C, D)>, w: [f32x8x8; 3], screen: [f32x8x8; 3]) -> (AO, BO, CO, DO) {
+        (InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w, screen))
     }
 }
 
+impl<A, B, C, D, E, AO, BO, CO, DO, EO> InterpolateWide for (A, B, C, D, E)
+    where A: InterpolateWide<Wide=AO>,
+          B: InterpolateWide<Wide=BO>,
+          C: InterpolateWide<Wide=CO>,
+          D: InterpolateWide<Wide=DO>,
+          E: InterpolateWide<Wide=EO> {
+    type Wide = (AO, BO, CO, DO, EO);
+    #[inline]
+    fn interpolate_wide(src: Triangle<&(A, B, C, D, E)>, w: [f32x8x8; 3]) -> (AO, BO, CO, DO, EO) {
+        (InterpolateWide::interpolate_wide(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w))
+    }
+
+    #[inline]
+    fn interpolate_wide_screen(src: Triangle<&(A, B, C, D, E)>, w: [f32x8x8; 3], screen: [f32x8x8; 3]) -> (AO, BO, CO, DO, EO) {
+        (InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w, screen))
+    }
+}
+
+impl<A, B, C, D, E, F, AO, BO, CO, DO, EO, FO> InterpolateWide for (A, B, C, D, E, F)
+    where A: InterpolateWide<Wide=AO>,
+          B: InterpolateWide<Wide=BO>,
+          C: InterpolateWide<Wide=CO>,
+          D: InterpolateWide<Wide=DO>,
+          E: InterpolateWide<Wide=EO>,
+          F: InterpolateWide<Wide=FO> {
+    type Wide = (AO, BO, CO, DO, EO, FO);
+    #[inline]
+    fn interpolate_wide(src: Triangle<&(A, B, C, D, E, F)>, w: [f32x8x8; 3]) -> (AO, BO, CO, DO, EO, FO) {
+        (InterpolateWide::interpolate_wide(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.5, &src.y.5, &src.z.5), w))
+    }
+
+    #[inline]
+    fn interpolate_wide_screen(src: Triangle<&(A, B, C, D, E, F)>, w: [f32x8x8; 3], screen: [f32x8x8; 3]) -> (AO, BO, CO, DO, EO, FO) {
+        (InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.5, &src.y.5, &src.z.5), w, screen))
+    }
+}
+
+impl<A, B, C, D, E, F, G, AO, BO, CO, DO, EO, FO, GO> InterpolateWide for (A, B, C, D, E, F, G)
+    where A: InterpolateWide<Wide=AO>,
+          B: InterpolateWide<Wide=BO>,
+          C: InterpolateWide<Wide=CO>,
+          D: InterpolateWide<Wide=DO>,
+          E: InterpolateWide<Wide=EO>,
+          F: InterpolateWide<Wide=FO>,
+          G: InterpolateWide<Wide=GO> {
+    type Wide = (AO, BO, CO, DO, EO, FO, GO);
+    #[inline]
+    fn interpolate_wide(src: Triangle<&(A, B, C, D, E, F, G)>, w: [f32x8x8; 3]) -> (AO, BO, CO, DO, EO, FO, GO) {
+        (InterpolateWide::interpolate_wide(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.5, &src.y.5, &src.z.5), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.6, &src.y.6, &src.z.6), w))
+    }
+
+    #[inline]
+    fn interpolate_wide_screen(src: Triangle<&(A, B, C, D, E, F, G)>, w: [f32x8x8; 3], screen: [f32x8x8; 3]) -> (AO, BO, CO, DO, EO, FO, GO) {
+        (InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.5, &src.y.5, &src.z.5), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.6, &src.y.6, &src.z.6), w, screen))
+    }
+}
+
+impl<A, B, C, D, E, F, G, H, AO, BO, CO, DO, EO, FO, GO, HO> InterpolateWide for (A, B, C, D, E, F, G, H)
+    where A: InterpolateWide<Wide=AO>,
+          B: InterpolateWide<Wide=BO>,
+          C: InterpolateWide<Wide=CO>,
+          D: InterpolateWide<Wide=DO>,
+          E: InterpolateWide<Wide=EO>,
+          F: InterpolateWide<Wide=FO>,
+          G: InterpolateWide<Wide=GO>,
+          H: InterpolateWide<Wide=HO> {
+    type Wide = (AO, BO, CO, DO, EO, FO, GO, HO);
+    #[inline]
+    fn interpolate_wide(src: Triangle<&(A, B, C, D, E, F, G, H)>, w: [f32x8x8; 3]) -> (AO, BO, CO, DO, EO, FO, GO, HO) {
+        (InterpolateWide::interpolate_wide(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.5, &src.y.5, &src.z.5), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.6, &src.y.6, &src.z.6), w),
+         InterpolateWide::interpolate_wide(Triangle::new(&src.x.7, &src.y.7, &src.z.7), w))
+    }
+
+    #[inline]
+    fn interpolate_wide_screen(src: Triangle<&(A, B, C, D, E, F, G, H)>, w: [f32x8x8; 3], screen: [f32x8x8; 3]) -> (AO, BO, CO, DO, EO, FO, GO, HO) {
+        (InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.0, &src.y.0, &src.z.0), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.1, &src.y.1, &src.z.1), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.2, &src.y.2, &src.z.2), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.3, &src.y.3, &src.z.3), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.4, &src.y.4, &src.z.4), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.5, &src.y.5, &src.z.5), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.6, &src.y.6, &src.z.6), w, screen),
+         InterpolateWide::interpolate_wide_screen(Triangle::new(&src.x.7, &src.y.7, &src.z.7), w, screen))
+    }
+}
@@ -1,4 +1,4 @@
-#![feature(simd, unboxed_closures, core, slice_patterns, step_by)]
+#![feature(simd, unboxed_closures, core, slice_patterns)]
 #![allow(non_camel_case_types)]
 
 extern crate image;
@@ -12,29 +12,52 @@ extern crate vec_map;
 
 use std::sync::Arc;
 use std::fmt::Debug;
+use std::ops::Range;
 use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use fibe::{Frontend, task, ResumableTask, WaitState, Schedule, IntoTask};
-use image::{GenericImage, ImageBuffer, Rgba};
+use image::{GenericImage, ImageBuffer, Rgba, Luma};
 use cgmath::*;
-use genmesh::{Triangle, MapVertex};
+use genmesh::{Triangle, Line, MapVertex};
 use future_pulse::*;
 use pulse::*;
 use snowstorm::channel::*;
 use vec_map::*;
 
-pub use tile::{TileGroup, Tile, Raster};
+pub use tile::{TileGroup, Tile, Raster, Setup, TileShader, PerPixel, Wide};
 use vmath::Dot;
 use f32x8::f32x8x8;
-pub use pipeline::{Fragment, Vertex, Mapping};
-pub use interpolate::{Flat, Interpolate};
+use line::{Corner, LineCoverage, LineDiamond};
+use point::PointSprite;
+use texture::Texels;
+use depth::DepthBuffer;
+use tile::Put;
+pub use pipeline::{Fragment, ContextFragment, FragmentContext, WideFragment, Derivatives, Vertex, Mapping};
+pub use interpolate::{Flat, NoPerspective, Interpolate, InterpolateWide};
+pub use state::{RasterState, FrameFormat, CullFace, FrontFace, DepthFunc, PolygonMode};
+pub use blend::{Blend, BlendChannel, BlendFactor, Equation, Blendable, Planes, FixedFunction};
+pub use stencil::{Stencil, StencilFace, StencilOp};
+pub use primitive::{Topology, Assembly, ProvokingVertex};
+pub use point::Point;
+pub use depth::{DepthFormat, DepthRange, DepthOutput, reversed_z};
+pub use texture::{Texture2D, Texture2DArray, TextureCube, Texture3D, Level, Volume, Sampler, Filter, Wrap};
 
 mod interpolate;
 mod pipeline;
+pub mod clip;
+pub mod line;
+pub mod point;
+mod state;
+mod blend;
+mod stencil;
+pub mod primitive;
 mod f32x4;
 pub mod f32x8;
 mod vmath;
 pub mod tile;
+mod texture;
+mod depth;
 
 
 #[cfg(dump)]
@@ -185,34 +208,401 @@ impl Barycentric {
     }
 }
 
+/// Fixed-point edge functions used to decide which pixels a triangle
+/// covers. Vertices are snapped to 1/256th of a pixel, and pixels that
+/// lie exactly on an edge are only covered if it is a top or left edge.
+/// This makes sure that pixels on an edge shared by two triangles are
+/// drawn exactly once.
+///
+/// Triangles are clipped to the guard band (see `clip::GUARD_BAND`)
+/// before their edges are set up, so the vertices are at most a few
+/// million sub-pixels from the frame and the products stay well inside
+/// of an `i64`.
+#[derive(Clone, Copy, Debug)]
+pub struct Edges {
+    a: [i64; 3],
+    b: [i64; 3],
+    c: [i64; 3]
+}
+
+/// The number of bits of sub-pixel precision
+const SUBPIXEL_BITS: u32 = 8;
+
+impl Edges {
+    /// Setup the edges for a triangle in normalized device coordinates,
+    /// `scale` is the size of a pixel.
+    pub fn new(t: Triangle<Vector2<f32>>, scale: Vector2<f32>) -> Edges {
+        let v0 = Edges::snap(t.x, scale);
+        let v1 = Edges::snap(t.y, scale);
+        let v2 = Edges::snap(t.z, scale);
+
+        // make the winding counter-clockwise so the inside is always positive
+        let area = (v1.0 - v0.0) * (v2.1 - v0.1) - (v1.1 - v0.1) * (v2.0 - v0.0);
+        let (v1, v2) = if area < 0 { (v2, v1) } else { (v1, v2) };
+
+        let mut edges = Edges {
+            a: [0; 3],
+            b: [0; 3],
+            c: [0; 3]
+        };
+
+        for (i, &(s, e)) in [(v0, v1), (v1, v2), (v2, v0)].iter().enumerate() {
+            let (dx, dy) = (e.0 - s.0, e.1 - s.1);
+
+            // y points up, so on a counter-clockwise triangle the left
+            // edges point down and the top edges point left
+            let top_left = dy < 0 || (dy == 0 && dx < 0);
+
+            edges.a[i] = -dy;
+            edges.b[i] = dx;
+            edges.c[i] = dy * s.0 - dx * s.1 - if top_left { 0 } else { 1 };
+        }
+
+        edges
+    }
+
+    #[inline]
+    fn snap(v: Vector2<f32>, scale: Vector2<f32>) -> (i64, i64) {
+        let one = (1 << SUBPIXEL_BITS) as f32;
+        ((((v.x + 1.) / scale.x) * one).round() as i64,
+         (((v.y + 1.) / scale.y) * one).round() as i64)
+    }
+
+    /// The coverage mask of the 8x8 pixels starting at `pos`
+    #[inline]
+    pub fn mask(&self, pos: Vector2<f32>, scale: Vector2<f32>) -> u64 {
+        let (ox, oy) = Edges::snap(pos, scale);
+        let mut mask = !0u64;
+
+        for i in 0..3 {
+            let (a, b) = (self.a[i], self.b[i]);
+            let (sx, sy) = (a << SUBPIXEL_BITS, b << SUBPIXEL_BITS);
+            let e = a * ox + b * oy + self.c[i];
+
+            // the edge is linear, so the corners of the tile bound it
+            let corners = [e, e + 7 * sx, e + 7 * sy, e + 7 * (sx + sy)];
+            if corners.iter().all(|&c| c >= 0) {
+                continue;
+            } else if corners.iter().all(|&c| c < 0) {
+                return 0;
+            }
+
+            // the sign bit is set for the pixels outside of the edge,
+            // there are no branches so the rows can be vectorised
+            let mut outside = 0u64;
+            for y in 0..8 {
+                let row = e + y as i64 * sy;
+                for x in 0..8 {
+                    let bit = ((row + x as i64 * sx) as u64) >> 63;
+                    outside |= bit << (y * 8 + x);
+                }
+            }
+            mask &= !outside;
+        }
+        mask
+    }
+}
+
+/// Block until every signal has been pulsed, a frame without
+/// any pixels has no tiles and nothing to wait for.
+fn wait_all(signals: &[Signal]) {
+    if !signals.is_empty() {
+        Barrier::new(signals).wait().unwrap();
+    }
+}
+
+/// The number of vertices transformed by a single task
+const VERTEX_BATCH: usize = 1024;
+
 pub struct Frame<P> {
     pub width: u32,
     pub height: u32,
     pub tile: Vec<Vec<Future<Box<TileGroup<P>>>>>,
+    format: FrameFormat,
+    /// blending and coverage for colours that implement `Planes`
+    fixed: Option<FixedFunction<P>>,
     pool: Frontend
 }
 
+/// A clipped and projected triangle that is sent to every tile it
+/// touches. The source vertices are shared between all of the tiles.
+struct Primitive<T> {
+    clip: Triangle<Vector3<f32>>,
+    inv_w: Vector3<f32>,
+    weights: Triangle<[f32; 3]>,
+    front: bool,
+    coverage: Option<LineCoverage>,
+    diamond: Option<LineDiamond>,
+    point: Option<PointSprite>,
+    src: Source<T>
+}
+
+/// The vertices a primitive was made from. Indexed triangles keep their
+/// indices into the vertex array of the draw, which is shared by all of them.
+enum Source<T> {
+    Triangle(Arc<Triangle<T>>),
+    Indexed(Arc<Vec<T>>, Triangle<u32>)
+}
+
+impl<T> Clone for Source<T> {
+    fn clone(&self) -> Source<T> {
+        match *self {
+            Source::Triangle(ref t) => Source::Triangle(t.clone()),
+            Source::Indexed(ref v, i) => Source::Indexed(v.clone(), i)
+        }
+    }
+}
+
+impl<T> Clone for Primitive<T> {
+    fn clone(&self) -> Primitive<T> {
+        Primitive {
+            clip: self.clip.clone(),
+            inv_w: self.inv_w,
+            weights: self.weights.clone(),
+            front: self.front,
+            coverage: self.coverage,
+            diamond: self.diamond,
+            point: self.point,
+            src: self.src.clone()
+        }
+    }
+}
+
+impl<T> Primitive<T> {
+    /// A triangle of the quad that a line or a point was expanded into
+    fn from_corners(t: Triangle<Corner>,
+                    front: bool,
+                    coverage: Option<LineCoverage>,
+                    diamond: Option<LineDiamond>,
+                    point: Option<PointSprite>,
+                    src: Source<T>) -> Primitive<T> {
+        Primitive {
+            clip: t.map_vertex(|v| v.pos),
+            inv_w: Vector3::new(t.x.inv_w, t.y.inv_w, t.z.inv_w),
+            weights: t.map_vertex(|v| v.weight),
+            front: front,
+            coverage: coverage,
+            diamond: diamond,
+            point: point,
+            src: src
+        }
+    }
+}
+
+/// Clip and cull a triangle that is in homogeneous clip space and push
+/// the primitives it is drawn with to `out`. For the outline polygon
+/// modes the edges or the vertices are expanded into quads instead,
+/// `scale` is the size of a pixel. `diagonal` is the edge that
+/// `hide_diagonals` skips, edge `i` runs from vertex `i` to the next.
+fn clip_space<T>(state: &RasterState,
+                 scale: Vector2<f32>,
+                 t: Triangle<Vector4<f32>>,
+                 diagonal: usize,
+                 src: Source<T>,
+                 out: &mut Vec<Primitive<T>>) {
+
+    if state.polygon_mode == PolygonMode::Fill {
+        for c in clip::clip_triangle_range(t, state.depth_range) {
+            let clip = c.map_vertex(|v| v.pos.truncate().div_s(v.pos.w));
+            let front = match state.facing(&clip) {
+                Some(front) => front,
+                None => continue
+            };
+
+            out.push(Primitive {
+                clip: clip,
+                inv_w: Vector3::new(c.x.pos.w.recip(), c.y.pos.w.recip(), c.z.pos.w.recip()),
+                weights: c.map_vertex(|v| v.weight),
+                front: front,
+                coverage: None,
+                diamond: None,
+                point: None,
+                src: src.clone()
+            });
+        }
+        return;
+    }
+
+    // every part of a clipped triangle faces the same way, the
+    // triangle is culled before its outline is expanded
+    let front = clip::clip_triangle_range(t, state.depth_range).next().and_then(|c| {
+        state.facing(&c.map_vertex(|v| v.pos.truncate().div_s(v.pos.w)))
+    });
+    let front = match front {
+        Some(front) => front,
+        None => return
+    };
+
+    let pos = [t.x, t.y, t.z];
+    let weight = [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+
+    match state.polygon_mode {
+        PolygonMode::Line => {
+            // the edges of the unclipped triangle are expanded and each
+            // line is clipped on its own, so clipping adds no new edges
+            for i in 0..3 {
+                if state.hide_diagonals && i == diagonal {
+                    continue;
+                }
+                let j = (i + 1) % 3;
+                let quad = line::expand(pos[i], pos[j], weight[i], weight[j],
+                                        state.line_width, state.line_smooth,
+                                        state.depth_range, scale);
+                if let Some((quad, coverage, diamond)) = quad {
+                    for &t in quad.iter() {
+                        out.push(Primitive::from_corners(t, front, coverage, diamond, None, src.clone()));
+                    }
+                }
+            }
+        }
+        PolygonMode::Point => {
+            for i in 0..3 {
+                let quad = point::expand(pos[i], weight[i], state.point_size,
+                                         state.point_round, state.depth_range, scale);
+                if let Some((quad, sprite)) = quad {
+                    for &t in quad.iter() {
+                        out.push(Primitive::from_corners(t, front, None, None, Some(sprite), src.clone()));
+                    }
+                }
+            }
+        }
+        PolygonMode::Fill => ()
+    }
+}
+
+/// The columns and the rows of the tiles that the bounding box of a
+/// projected triangle touches in a frame of `w` by `h` pixels, `None`
+/// if it is entirely off screen.
+fn tile_range(clip: &Triangle<Vector3<f32>>, w: u32, h: u32) -> Option<(Range<usize>, Range<usize>)> {
+    use std::cmp::{min, max};
+    let (hh, wh) = (h as f32 / 2., w as f32 / 2.);
+
+    let clip2 = clip.map_vertex(|v| Vector2::new(v.x * wh + wh, v.y * hh + hh));
+    let max_x = clip2.x.x.ceil().partial_max(clip2.y.x.ceil().partial_max(clip2.z.x.ceil()));
+    let min_x = clip2.x.x.floor().partial_min(clip2.y.x.floor().partial_min(clip2.z.x.floor()));
+    let max_y = clip2.x.y.ceil().partial_max(clip2.y.y.ceil().partial_max(clip2.z.y.ceil()));
+    let min_y = clip2.x.y.floor().partial_min(clip2.y.y.floor().partial_min(clip2.z.y.floor()));
+
+    let min_x = max(min_x as i32, 0);
+    let min_y = max(min_y as i32, 0);
+    let max_x = min(max_x as i32, w as i32 - 1);
+    let max_y = min(max_y as i32, h as i32 - 1);
+
+    if min_x > max_x || min_y > max_y {
+        return None;
+    }
+
+    Some(((min_x / 32_) as usize..(max_x / 32_ + 1) as usize,
+          (min_y / 32_) as usize..(max_y / 32_ + 1) as usize))
+}
+
+/// Panic if any index other than the restart index of `assembly`
+/// is out of range for `count` vertices
+fn check_indices(indices: &[u32], count: usize, assembly: &Assembly) {
+    for &i in indices.iter() {
+        if i as usize >= count && Some(i) != assembly.restart {
+            panic!("index {} is out of range for {} vertices", i, count);
+        }
+    }
+}
+
+/// A tile that was taken out of a frame, `set` hands it back
+struct TileSlot<P> {
+    tile: Future<Box<TileGroup<P>>>,
+    set: future_pulse::Set<Box<TileGroup<P>>>
+}
+
+impl<P: Send+'static> TileSlot<P> {
+    /// Hand the tile back once it is ready, without drawing to it
+    fn forward(self, sched: &mut Schedule) {
+        let TileSlot { tile, set } = self;
+        let signal = tile.signal();
+        task(move |_| {
+            set.set(tile.get());
+        }).after(signal).start(sched);
+    }
+}
+
+/// The frame and the state that a tile worker draws with
+#[derive(Clone, Copy)]
+struct TileConfig<P> {
+    width: u32,
+    height: u32,
+    state: RasterState,
+    fixed: Option<FixedFunction<P>>
+}
+
+impl<P: Copy+Send+'static> TileConfig<P> {
+    /// Start a worker on the tile in `slot` at `(x, y)` that rasterizes
+    /// every primitive sent to it, the tile is handed back once the
+    /// sender is dropped.
+    fn open<F, T>(self, x: usize, y: usize, slot: TileSlot<P>, fragment: Arc<F>, sched: &mut Schedule) -> Sender<Primitive<T>>
+        where T: Clone + Send + Sync + 'static + Debug,
+              F: TileShader<T, P> + Send + Sync + 'static {
+
+        let (hh, wh) = (self.height as f32 / 2., self.width as f32 / 2.);
+        let scale = Vector2::new(wh.recip(), hh.recip());
+        let TileSlot { tile, set } = slot;
+        let signal = tile.signal();
+
+        let (tx, rx) = channel();
+        task(move |sched| {
+            let config = self;
+            let signal = rx.signal();
+            RasterWorker {
+                tile: Some(tile.get()),
+                polygons: rx,
+                scale: scale,
+                state: config.state,
+                fixed: config.fixed,
+                pos: Vector2::new(((x*32) as f32 - wh) * scale.x,
+                                  ((y*32) as f32 - hh) * scale.y),
+                fragment: fragment,
+                result: Some(set)
+            }.after(signal).start(sched);
+        }).after(signal).start(sched);
+        tx
+    }
+}
+
 struct RasterWorker<P: Send, T: Send+Sync, F> {
     tile: Option<Box<TileGroup<P>>>,
-    polygons: Receiver<(Triangle<Vector3<f32>>, Triangle<T>)>,
+    polygons: Receiver<Primitive<T>>,
     pos: Vector2<f32>,
     scale: Vector2<f32>,
+    state: RasterState,
+    fixed: Option<FixedFunction<P>>,
     fragment: Arc<F>,
     result: Option<future_pulse::Set<Box<TileGroup<P>>>>
 }
 
-impl<T: Send+Sync, P: Send+Copy, F, O> ResumableTask for RasterWorker<P, T, F>
-    where F: Fragment<O, Color=P>+Send+Sync,
-          T: Interpolate<Out=O>+Send+Sync+Debug
+impl<T: Send+Sync, P: Send+Copy, F> ResumableTask for RasterWorker<P, T, F>
+    where F: TileShader<T, P>+Send+Sync,
+          T: Clone+Send+Sync+Debug
 
 {
     fn resume(&mut self, _: &mut Schedule) -> WaitState {
         let mut tile = self.tile.take().unwrap();
 
-        while let Some(&(ref clip, ref or)) = self.polygons.try_recv() {
-            let z = Vector3::new(clip.x.z, clip.y.z, clip.z.z);
-            let bary = Barycentric::new(clip.map_vertex(|v| v.truncate()));
-            tile.raster(self.pos, self.scale, &z, &bary, or, &*self.fragment);
+        while let Some(p) = self.polygons.try_recv() {
+            let clip = p.clip;
+            let setup = Setup {
+                z: Vector3::new(clip.x.z, clip.y.z, clip.z.z),
+                inv_w: p.inv_w,
+                bary: Barycentric::new(clip.map_vertex(|v| v.truncate())),
+                edges: Edges::new(clip.map_vertex(|v| v.truncate()), self.scale),
+                weights: p.weights,
+                front: p.front,
+                coverage: p.coverage,
+                diamond: p.diamond,
+                point: p.point
+            };
+            // the vertices are borrowed from the source, they are never copied
+            let t = match p.src {
+                Source::Triangle(ref t) => Triangle::new(&t.x, &t.y, &t.z),
+                Source::Indexed(ref v, i) => Triangle::new(&v[i.x as usize], &v[i.y as usize], &v[i.z as usize])
+            };
+            tile.raster(self.pos, self.scale, &self.state, &setup, self.fixed, t, &*self.fragment);
         }
 
         if self.polygons.closed() {
@@ -226,20 +616,43 @@ impl<T: Send+Sync, P: Send+Copy, F, O> ResumableTask for RasterWorker<P, T, F>
 }
 
 impl<P: Copy+Sync+Send+'static> Frame<P> {
-    pub fn new(width: u32, height: u32, p: P) -> Frame<P> {
+    /// Create a frame of a colour that does not implement `Planes`, that
+    /// stores the samples and planes of `format`. Its colours can only be
+    /// blended by `Fragment::blend`, the fixed function blend state and
+    /// smooth lines are not available.
+    ///
+    /// Panics if `format.samples` is not 1, 2, 4 or 8.
+    pub fn custom(width: u32, height: u32, format: FrameFormat, p: P) -> Frame<P> {
+        match format.samples {
+            1 | 2 | 4 | 8 => (),
+            n => panic!("frames have 1, 2, 4 or 8 samples per pixel, not {}", n)
+        }
+
         Frame {
             width: width,
             height: height,
-            tile: (0..(height / 32_)).map(
-                |_| (0..(width / 32_)).map(
-                    |_| Future::from_value(Box::new(TileGroup::new(p)))
+            tile: (0..(height + 31) / 32_).map(
+                |_| (0..(width + 31) / 32_).map(
+                    |_| Future::from_value(Box::new(TileGroup::with_format(p, &format)))
                 ).collect()
             ).collect(),
+            format: format,
+            fixed: None,
             pool: Frontend::new()
         }
     }
 
+    /// Clear the color to `p`, the depth to the far plane of the
+    /// `FrameFormat` and the stencil to 0
     pub fn clear(&mut self, p: P) {
+        let far = self.format.far();
+        self.clear_with(p, far, 0);
+    }
+
+    /// Clear the color to `p` and the depth and the stencil to the given
+    /// values. The depth is window depth from 0 to 1 and it is stored in
+    /// the `DepthFormat` of the frame like the depth of any fragment.
+    pub fn clear_with(&mut self, p: P, depth: f32, stencil: u8) {
         use std::mem;
         for row in self.tile.iter_mut() {
             for tile in row.iter_mut() {
@@ -248,90 +661,432 @@ impl<P: Copy+Sync+Send+'static> Frame<P> {
                 let signal = new.signal();
                 task(move |_| {
                     let mut t = new.get();
-                    t.clear(p);
+                    t.clear(p, depth, stencil);
                     set.set(t);
                 }).after(signal).start(&mut self.pool);
             }
         }
     }
 
+    /// Rasterize the triangles using the default `RasterState`
     pub fn raster<S, F, T, O>(&mut self, poly: S, fragment: F)
         where S: Iterator<Item=Triangle<T>>,
               T: Clone + Interpolate<Out=O> + FetchPosition + Send + Sync + 'static + Debug,
-              F: Fragment<O, Color=P> + Send + Sync + 'static {
+              F: ContextFragment<O, Color=P> + Send + Sync + 'static {
 
-        use std::cmp::{min, max};
-        let h = self.height;
-        let w = self.width;
-        let (hf, wf) = (h as f32, w as f32);
-        let (hh, wh) = (hf/2., wf/2.);
-        let scale = Vector2::new(hh.recip(), wh.recip());
+        self.raster_with(&RasterState::new(), poly, fragment);
+    }
 
-        let fragment = Arc::new(fragment);
+    pub fn raster_with<S, F, T, O>(&mut self, state: &RasterState, poly: S, fragment: F)
+        where S: Iterator<Item=Triangle<T>>,
+              T: Clone + Interpolate<Out=O> + FetchPosition + Send + Sync + 'static + Debug,
+              F: ContextFragment<O, Color=P> + Send + Sync + 'static {
 
-        let mut queue = VecMap::new();
-        let width = self.width as usize;
-        let index = |x, y| {width * y + x};
-
-        let mut command = |x, y, t| {
-            let i = index(x, y);
-            if queue.get(&i).is_none() {
-                use std::mem;
-                let (tx, rx) = channel();
-                let (mut future, set) = Future::new();
-                let fragment = fragment.clone();
-                mem::swap(&mut self.tile[x as usize][y as usize], &mut future);
-                let signal = future.signal();
-
-                task(move |sched| {
-                    let wh = wh;
-                    let hh = hh;
-                    let scale = scale;
-                    let signal = rx.signal();
-                    RasterWorker {
-                        tile: Some(future.get()),
-                        polygons: rx,
-                        scale: scale,
-                        pos: Vector2::new(((x*32) as f32 - wh) * scale.x,
-                                          ((y*32) as f32 - hh) * scale.y),
-                        fragment: fragment,
-                        result: Some(set)
-                    }.after(signal).start(sched);
-                }).after(signal).start(&mut self.pool);
-                queue.insert(i, tx);
-            }
+        let poly = poly.map(|or| {
+            let t = or.clone().map_vertex(|v| {
+                let v = v.position();
+                Vector4::new(v[0], v[1], v[2], v[3])
+            });
+            (t, 2, Source::Triangle(Arc::new(or)))
+        });
 
-            queue.get_mut(&i).unwrap().send(t);
-        };
+        self.raster_clip_space(state, poly, PerPixel(fragment));
+    }
+
+    /// Rasterize the triangles with a `WideFragment` using the default `RasterState`
+    pub fn raster_wide<S, F, T, O>(&mut self, poly: S, fragment: F)
+        where S: Iterator<Item=Triangle<T>>,
+              T: Clone + InterpolateWide<Wide=O> + FetchPosition + Send + Sync + 'static + Debug,
+              F: WideFragment<O, Color=P> + Send + Sync + 'static {
+
+        self.raster_wide_with(&RasterState::new(), poly, fragment);
+    }
 
-        for or in poly {
+    /// Rasterize the triangles shading a whole 8x8 tile at a time, the
+    /// attributes are interpolated into `f32x8x8` lanes so the fragment
+    /// shader can be vectorized. The lines and points of
+    /// `PolygonMode::Line` and `PolygonMode::Point` are shaded a tile at a
+    /// time as well, but a `WideFragment` does not get the position of a
+    /// fragment inside of a point sprite.
+    pub fn raster_wide_with<S, F, T, O>(&mut self, state: &RasterState, poly: S, fragment: F)
+        where S: Iterator<Item=Triangle<T>>,
+              T: Clone + InterpolateWide<Wide=O> + FetchPosition + Send + Sync + 'static + Debug,
+              F: WideFragment<O, Color=P> + Send + Sync + 'static {
+
+        let poly = poly.map(|or| {
             let t = or.clone().map_vertex(|v| {
                 let v = v.position();
                 Vector4::new(v[0], v[1], v[2], v[3])
             });
+            (t, 2, Source::Triangle(Arc::new(or)))
+        });
 
-            let clip = t.map_vertex(|v| v.truncate().div_s(v.w) );
+        self.raster_clip_space(state, poly, Wide(fragment));
+    }
 
-            if is_backface(clip) {
-                continue;
+    /// Rasterize indexed triangles using the default `RasterState`, `assembly`
+    /// can be a `Topology` or an `Assembly` for primitive restart and the
+    /// provoking vertex. Streams of vertices without indices can be
+    /// assembled with `Assembly::vertices` and passed to `raster`.
+    pub fn raster_indexed<F, T, O, A>(&mut self, vertices: Arc<Vec<T>>, indices: &[u32], assembly: A, fragment: F)
+        where A: Into<Assembly>,
+              T: Clone + Interpolate<Out=O> + FetchPosition + Send + Sync + 'static + Debug,
+              F: ContextFragment<O, Color=P> + Send + Sync + 'static {
+
+        self.raster_indexed_with(&RasterState::new(), vertices, indices, assembly, fragment);
+    }
+
+    /// Rasterize the triangles described by an index buffer. The vertices
+    /// are shared by every triangle and never copied, the triangles only
+    /// carry their indices. The position of each vertex is only fetched
+    /// once, no matter how many triangles share it.
+    ///
+    /// Panics if an index other than the restart index is out of range.
+    pub fn raster_indexed_with<F, T, O, A>(&mut self,
+                                           state: &RasterState,
+                                           vertices: Arc<Vec<T>>,
+                                           indices: &[u32],
+                                           assembly: A,
+                                           fragment: F)
+        where A: Into<Assembly>,
+              T: Clone + Interpolate<Out=O> + FetchPosition + Send + Sync + 'static + Debug,
+              F: ContextFragment<O, Color=P> + Send + Sync + 'static {
+
+        self.raster_shared(state, vertices, indices, assembly.into(), PerPixel(fragment));
+    }
+
+    /// Rasterize indexed triangles whose vertices are already shared
+    fn raster_shared<F, T>(&mut self,
+                           state: &RasterState,
+                           vertices: Arc<Vec<T>>,
+                           indices: &[u32],
+                           assembly: Assembly,
+                           fragment: F)
+        where T: Clone + FetchPosition + Send + Sync + 'static + Debug,
+              F: TileShader<T, P> + Send + Sync + 'static {
+
+        let positions: Vec<Vector4<f32>> = vertices.iter().map(|v| {
+            let v = v.position();
+            Vector4::new(v[0], v[1], v[2], v[3])
+        }).collect();
+
+        check_indices(indices, positions.len(), &assembly);
+        let poly = assembly.indices(indices).diagonals().map(|(i, diagonal)| {
+            let (x, y, z) = (i.x as usize, i.y as usize, i.z as usize);
+            (Triangle::new(positions[x], positions[y], positions[z]),
+             diagonal,
+             Source::Indexed(vertices.clone(), i))
+        });
+
+        self.raster_clip_space(state, poly, fragment);
+    }
+
+    /// Run the vertex shader over `vertices` and rasterize the results using
+    /// the default `RasterState`
+    pub fn draw<V, VS, F, T, O, A>(&mut self,
+                                   vertices: Arc<Vec<V>>,
+                                   indices: Arc<Vec<u32>>,
+                                   assembly: A,
+                                   vertex: VS,
+                                   fragment: F)
+        where A: Into<Assembly>,
+              V: Send + Sync + 'static,
+              VS: Vertex<V, Out=T> + Send + Sync + 'static,
+              T: Clone + Interpolate<Out=O> + Send + Sync + 'static + Debug,
+              F: ContextFragment<O, Color=P> + Send + Sync + 'static {
+
+        self.draw_with(&RasterState::new(), vertices, indices, assembly, vertex, fragment);
+    }
+
+    /// Run the vertex shader over `vertices` in parallel, every vertex is
+    /// only transformed once. The outputs are then assembled into triangles
+    /// using `indices` and rasterized. Nothing blocks, the triangles are
+    /// binned on the pool once every vertex has been transformed.
+    ///
+    /// Panics if an index other than the restart index is out of range.
+    pub fn draw_with<V, VS, F, T, O, A>(&mut self,
+                                        state: &RasterState,
+                                        vertices: Arc<Vec<V>>,
+                                        indices: Arc<Vec<u32>>,
+                                        assembly: A,
+                                        vertex: VS,
+                                        fragment: F)
+        where A: Into<Assembly>,
+              V: Send + Sync + 'static,
+              VS: Vertex<V, Out=T> + Send + Sync + 'static,
+              T: Clone + Interpolate<Out=O> + Send + Sync + 'static + Debug,
+              F: ContextFragment<O, Color=P> + Send + Sync + 'static {
+
+        let assembly: Assembly = assembly.into();
+        check_indices(&indices, vertices.len(), &assembly);
+        self.check_state(state);
+
+        let (batches, signals) = self.transform(vertices, vertex);
+
+        // the primitives are not known until the vertices are transformed,
+        // so every tile is taken and handed to the bin task. Workers are
+        // only started on the tiles that are drawn to.
+        let fragment = Arc::new(PerPixel(fragment));
+        let config = self.tile_config(state);
+        let mut slots = Vec::new();
+        for y in 0..self.tile.len() {
+            for x in 0..self.tile[y].len() {
+                slots.push(Some(self.take_tile(x, y)));
+            }
+        }
+
+        let (w, h) = (self.width, self.height);
+        let width = ((w + 31) / 32_) as usize;
+        let scale = Vector2::new(2. / w as f32, 2. / h as f32);
+        let state = *state;
+
+        let mut bin = task(move |sched| {
+            let mut slots = slots;
+            let mut queue = VecMap::new();
+            let mut positions = Vec::new();
+            let mut outputs = Vec::new();
+            for batch in batches.into_iter() {
+                let (p, o) = batch.get();
+                positions.extend(p.into_iter());
+                outputs.extend(o.into_iter());
+            }
+            let outputs = Arc::new(outputs);
+
+            for (i, diagonal) in assembly.indices(&indices).diagonals() {
+                let (x, y, z) = (i.x as usize, i.y as usize, i.z as usize);
+                let t = Triangle::new(positions[x], positions[y], positions[z]);
+                let mut prims = Vec::new();
+                clip_space(&state, scale, t, diagonal, Source::Indexed(outputs.clone(), i), &mut prims);
+
+                for p in prims.into_iter() {
+                    if let Some((xs, ys)) = tile_range(&p.clip, w, h) {
+                        for y in ys {
+                            for x in xs.clone() {
+                                let i = width * y + x;
+                                if queue.get(&i).is_none() {
+                                    let slot = slots[i].take().unwrap();
+                                    queue.insert(i, config.open(x, y, slot, fragment.clone(), sched));
+                                }
+                                queue.get_mut(&i).unwrap().send(p.clone());
+                            }
+                        }
+                    }
+                }
+            }
+
+            // the tiles that nothing was drawn to are handed straight back
+            for slot in slots.into_iter().filter_map(|s| s) {
+                slot.forward(sched);
             }
+            // dropping the senders lets the tile workers finish
+        });
+        for signal in signals.into_iter() {
+            bin = bin.after(signal);
+        }
+        bin.start(&mut self.pool);
+    }
+
+    /// Run the vertex shader on the pool in batches of `VERTEX_BATCH`. Returns
+    /// the positions and the outputs of each batch, and the signals that are
+    /// pulsed once the batches are done.
+    fn transform<V, VS, T>(&mut self, vertices: Arc<Vec<V>>, vertex: VS)
+        -> (Vec<Future<(Vec<Vector4<f32>>, Vec<T>)>>, Vec<Signal>)
+        where V: Send + Sync + 'static,
+              VS: Vertex<V, Out=T> + Send + Sync + 'static,
+              T: Send + 'static {
+
+        use std::cmp::min;
 
-            let clip2 = clip.map_vertex(|v| Vector2::new(v.x * wh + wh, v.y * hh + hh));
-            let max_x = clip2.x.x.ceil().partial_max(clip2.y.x.ceil().partial_max(clip2.z.x.ceil()));
-            let min_x = clip2.x.x.floor().partial_min(clip2.y.x.floor().partial_min(clip2.z.x.floor()));
-            let max_y = clip2.x.y.ceil().partial_max(clip2.y.y.ceil().partial_max(clip2.z.y.ceil()));
-            let min_y = clip2.x.y.floor().partial_min(clip2.y.y.floor().partial_min(clip2.z.y.floor()));
-
-            let min_x = (max(min_x as i32, 0) as u32) & (0xFFFFFFFF & !0x1F_);
-            let min_y = (max(min_y as i32, 0) as u32) & (0xFFFFFFFF & !0x1F_);
-            let max_x = min(max_x as u32, w-0x1F_);
-            let max_y = min(max_y as u32, h-0x1F_);
-
-            for y in (min_y..max_y+1).step_by(32) {
-                for x in (min_x..max_x+1).step_by(32) {
-                    let ix = (x / 32_) as usize;
-                    let iy = (y / 32_) as usize;
-                    command(ix, iy, (clip.clone(), or.clone()));
+        let vertex = Arc::new(vertex);
+        let mut batches = Vec::new();
+        let mut signals = Vec::new();
+
+        let count = (vertices.len() + VERTEX_BATCH - 1) / VERTEX_BATCH;
+        for b in 0..count {
+            let start = b * VERTEX_BATCH;
+            let end = min(start + VERTEX_BATCH, vertices.len());
+            let (vertices, vertex) = (vertices.clone(), vertex.clone());
+            let (future, set) = Future::new();
+            signals.push(task(move |_| {
+                let mut positions = Vec::with_capacity(end - start);
+                let mut outputs = Vec::with_capacity(end - start);
+                for v in vertices[start..end].iter() {
+                    let (p, out) = vertex.vertex(v);
+                    positions.push(Vector4::new(p[0], p[1], p[2], p[3]));
+                    outputs.push(out);
+                }
+                set.set((positions, outputs));
+            }).start(&mut self.pool));
+            batches.push(future);
+        }
+
+        (batches, signals)
+    }
+
+    /// Draw lines using the default `RasterState`
+    pub fn raster_lines<S, F, T, O>(&mut self, lines: S, fragment: F)
+        where S: Iterator<Item=Line<T>>,
+              T: Clone + Interpolate<Out=O> + FetchPosition + Send + Sync + 'static + Debug,
+              F: ContextFragment<O, Color=P> + Send + Sync + 'static {
+
+        self.raster_lines_with(&RasterState::new(), lines, fragment);
+    }
+
+    /// Draw lines `state.line_width` pixels wide, the attributes are
+    /// interpolated from the first to the second vertex. Lines are
+    /// depth and stencil tested like front facing triangles but are
+    /// never culled.
+    pub fn raster_lines_with<S, F, T, O>(&mut self, state: &RasterState, lines: S, fragment: F)
+        where S: Iterator<Item=Line<T>>,
+              T: Clone + Interpolate<Out=O> + FetchPosition + Send + Sync + 'static + Debug,
+              F: ContextFragment<O, Color=P> + Send + Sync + 'static {
+
+        let scale = Vector2::new(2. / self.width as f32, 2. / self.height as f32);
+        let (width, smooth) = (state.line_width, state.line_smooth);
+
+        let prims = lines.flat_map(|l| {
+            let (a, b) = (l.x.position(), l.y.position());
+            let (a, b) = (Vector4::new(a[0], a[1], a[2], a[3]), Vector4::new(b[0], b[1], b[2], b[3]));
+            let src = Source::Triangle(Arc::new(Triangle::new(l.x, l.y.clone(), l.y)));
+
+            let quad = line::expand(a, b, [1., 0., 0.], [0., 1., 0.], width, smooth, state.depth_range, scale);
+            quad.into_iter().flat_map(move |(quad, coverage, diamond)| {
+                let src = src.clone();
+                quad.to_vec().into_iter().map(move |t| {
+                    Primitive::from_corners(t, true, coverage, diamond, None, src.clone())
+                })
+            })
+        });
+
+        self.bin(state, prims, PerPixel(fragment));
+    }
+
+    /// Draw point sprites using the default `RasterState`
+    pub fn raster_points<S, F, T, O>(&mut self, points: S, fragment: F)
+        where S: Iterator<Item=Point<T>>,
+              T: Clone + Interpolate<Out=O> + FetchPosition + Send + Sync + 'static + Debug,
+              F: ContextFragment<O, Color=P> + Send + Sync + 'static {
+
+        self.raster_points_with(&RasterState::new(), points, fragment);
+    }
+
+    /// Draw each point as a screen aligned square of its own size, the
+    /// fragments get their position in the sprite from `FragmentContext::point`. If
+    /// `state.point_round` is set only the circle inside of the square
+    /// is drawn.
+    pub fn raster_points_with<S, F, T, O>(&mut self, state: &RasterState, points: S, fragment: F)
+        where S: Iterator<Item=Point<T>>,
+              T: Clone + Interpolate<Out=O> + FetchPosition + Send + Sync + 'static + Debug,
+              F: ContextFragment<O, Color=P> + Send + Sync + 'static {
+
+        let scale = Vector2::new(2. / self.width as f32, 2. / self.height as f32);
+        let round = state.point_round;
+
+        let prims = points.flat_map(|p| {
+            let v = p.vertex.position();
+            let v = Vector4::new(v[0], v[1], v[2], v[3]);
+            let quad = point::expand(v, [1., 0., 0.], p.size, round, state.depth_range, scale);
+            let src = Source::Triangle(Arc::new(Triangle::new(p.vertex.clone(), p.vertex.clone(), p.vertex)));
+
+            quad.into_iter().flat_map(move |(quad, sprite)| {
+                let src = src.clone();
+                quad.to_vec().into_iter().map(move |t| {
+                    Primitive::from_corners(t, true, None, None, Some(sprite), src.clone())
+                })
+            })
+        });
+
+        self.bin(state, prims, PerPixel(fragment));
+    }
+
+    /// Clip and cull triangles that are in homogeneous clip space, or draw
+    /// their outlines for the other polygon modes
+    fn raster_clip_space<S, F, T>(&mut self, state: &RasterState, poly: S, fragment: F)
+        where S: Iterator<Item=(Triangle<Vector4<f32>>, usize, Source<T>)>,
+              T: Clone + Send + Sync + 'static + Debug,
+              F: TileShader<T, P> + Send + Sync + 'static {
+
+        let scale = Vector2::new(2. / self.width as f32, 2. / self.height as f32);
+
+        let prims = poly.flat_map(|(t, diagonal, src)| {
+            let mut out = Vec::new();
+            clip_space(state, scale, t, diagonal, src, &mut out);
+            out.into_iter()
+        });
+
+        self.bin(state, prims, fragment);
+    }
+
+    /// Panic if `state` needs something that this frame does not have
+    fn check_state(&self, state: &RasterState) {
+        if state.fixed_function() && self.fixed.is_none() {
+            panic!("blending and smooth lines need a colour that implements Planes, see Frame::custom");
+        }
+        if state.stencil.is_some() && !self.format.stencil {
+            panic!("the stencil test needs a frame with a stencil, see FrameFormat::stencil");
+        }
+    }
+
+    /// Start a worker on tile `(x, y)` that rasterizes every primitive sent
+    /// to it, the tile is handed back once the sender is dropped.
+    fn open_tile<F, T>(&mut self, x: usize, y: usize, state: &RasterState, fragment: Arc<F>) -> Sender<Primitive<T>>
+        where T: Clone + Send + Sync + 'static + Debug,
+              F: TileShader<T, P> + Send + Sync + 'static {
+
+        let slot = self.take_tile(x, y);
+        let config = self.tile_config(state);
+        config.open(x, y, slot, fragment, &mut self.pool)
+    }
+
+    /// Take tile `(x, y)` out of the frame, later work on the tile
+    /// waits until the slot is handed back.
+    fn take_tile(&mut self, x: usize, y: usize) -> TileSlot<P> {
+        use std::mem;
+        let (mut future, set) = Future::new();
+        mem::swap(&mut self.tile[y][x], &mut future);
+        TileSlot {
+            tile: future,
+            set: set
+        }
+    }
+
+    /// What the tile workers of a draw with `state` need to know
+    fn tile_config(&self, state: &RasterState) -> TileConfig<P> {
+        TileConfig {
+            width: self.width,
+            height: self.height,
+            state: *state,
+            fixed: self.fixed
+        }
+    }
+
+    /// Send each primitive to every tile that its bounding box touches
+    fn bin<S, F, T>(&mut self, state: &RasterState, prims: S, fragment: F)
+        where S: Iterator<Item=Primitive<T>>,
+              T: Clone + Send + Sync + 'static + Debug,
+              F: TileShader<T, P> + Send + Sync + 'static {
+
+        self.check_state(state);
+
+        let fragment = Arc::new(fragment);
+        let (w, h) = (self.width, self.height);
+        let width = ((w + 31) / 32_) as usize;
+        let mut queue = VecMap::new();
+
+        for p in prims {
+            let (xs, ys) = match tile_range(&p.clip, w, h) {
+                Some(range) => range,
+                None => continue
+            };
+
+            for y in ys {
+                for x in xs.clone() {
+                    let i = width * y + x;
+                    if queue.get(&i).is_none() {
+                        let tx = self.open_tile(x, y, state, fragment.clone());
+                        queue.insert(i, tx);
+                    }
+                    queue.get_mut(&i).unwrap().send(p.clone());
                 }
             }
         }
@@ -344,6 +1099,8 @@ impl<P: Copy+Sync+Send+'static> Frame<P> {
 
         assert!(src.width == self.width);
         assert!(src.height == self.height);
+        assert!(src.format.samples == self.format.samples,
+                "both frames must have the same number of samples");
 
         let pixel = Arc::new(pixel);
 
@@ -373,30 +1130,232 @@ impl<P: Copy+Sync+Send+'static> Frame<P> {
             }
         }
     }
-}
 
-impl Frame<Rgba<u8>> {
-    pub fn into_image(&mut self, img: ImageBuffer<Rgba<u8>, Vec<u8>>) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    /// Make every later draw to this frame wait until `texture` is ready,
+    /// so its fragment shaders can sample it. Nothing blocks, the tiles
+    /// of this frame are simply not touched until then.
+    pub fn bind<Q>(&mut self, texture: &FrameTexture<Q>) {
+        use std::mem;
+        for row in self.tile.iter_mut() {
+            for tile in row.iter_mut() {
+                let (mut new, set) = Future::new();
+                mem::swap(tile, &mut new);
+                let signal = new.signal();
+                task(move |_| {
+                    set.set(new.get());
+                }).after(signal).after(texture.signal()).start(&mut self.pool);
+            }
+        }
+    }
+
+    /// Start a task for each tile that passes it to `f` with `dst` once
+    /// the pending draws to the tile have finished. Returns the signals
+    /// of the tasks, `dst` must outlive them.
+    unsafe fn read_tiles<W: Send>(&mut self, dst: *mut W, f: fn(&TileGroup<P>, u32, u32, &mut W)) -> Vec<Signal> {
         use std::mem;
-        let buffer = UnsafeCell::new(img);
         let mut signals = Vec::new();
 
-        for (x, row) in self.tile.iter_mut().enumerate() {
-            for (y, tile) in row.iter_mut().enumerate() {
+        for (y, row) in self.tile.iter_mut().enumerate() {
+            for (x, tile) in row.iter_mut().enumerate() {
                 let (mut new, tx_self) = Future::new();
                 mem::swap(tile, &mut new);
-                let buff: &mut ImageBuffer<_, Vec<_>> = unsafe { mem::transmute(buffer.get()) };
+                let buff: &mut W = mem::transmute(dst);
                 let signal = new.signal();
                 signals.push(task(move |_| {
                     let t = new.get();
-                    t.write((x*32_) as u32, (y*32_) as u32, buff);
+                    f(&*t, (x*32_) as u32, (y*32_) as u32, buff);
                     tx_self.set(t);
                 }).after(signal).start(&mut self.pool));
             }
         }
+        signals
+    }
+
+    /// Wait for the pending draws to finish and read the window
+    /// depth of every pixel.
+    fn read_depth(&mut self) -> DepthBuffer {
+        fn write<P: Copy>(t: &TileGroup<P>, x: u32, y: u32, dst: &mut DepthBuffer) {
+            t.write_depth(x, y, dst);
+        }
+
+        let mut buffer = DepthBuffer::new(self.width, self.height);
+        let signals = unsafe { self.read_tiles(&mut buffer, write::<P>) };
+        wait_all(&signals);
+        buffer
+    }
+
+    /// The depth of every pixel converted by `output`, row by row
+    /// starting at the top of the frame like `to_image`. Multisampled
+    /// frames return the depth of the first sample.
+    pub fn depth_to_vec(&mut self, output: DepthOutput) -> Vec<f32> {
+        let range = output.range();
+        self.read_depth().data.into_iter().map(|w| output.convert(range.ndc(w))).collect()
+    }
+
+    /// The depth of the frame as a 16 bit grayscale image, linear
+    /// depth is scaled so the near plane is black and the far plane white.
+    pub fn depth_to_image(&mut self, output: DepthOutput) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+        let (width, height, range) = (self.width, self.height, output.range());
+        let data = self.read_depth().data.into_iter()
+            .map(|w| (output.normalize(range.ndc(w)) * 65535.).round() as u16)
+            .collect();
+        ImageBuffer::from_raw(width, height, data).unwrap()
+    }
+}
+
+impl<P: Planes+Sync+Send+'static> Frame<P> {
+    pub fn new(width: u32, height: u32, p: P) -> Frame<P> {
+        Frame::new_multisample(width, height, 1, p)
+    }
+
+    /// Create a frame with 1, 2, 4 or 8 samples per pixel. Coverage, depth
+    /// and stencil are evaluated per sample, fragments are shaded once per pixel.
+    ///
+    /// Panics if `samples` is any other count.
+    pub fn new_multisample(width: u32, height: u32, samples: usize, p: P) -> Frame<P> {
+        Frame::with_format(width, height, FrameFormat { samples: samples, .. FrameFormat::new() }, p)
+    }
+
+    /// Create a frame that stores the samples and planes of `format`, the
+    /// fixed function blend state and smooth lines of `RasterState` can
+    /// be used with it.
+    ///
+    /// Panics if `format.samples` is not 1, 2, 4 or 8.
+    pub fn with_format(width: u32, height: u32, format: FrameFormat, p: P) -> Frame<P> {
+        Frame {
+            fixed: Some(FixedFunction::new()),
+            .. Frame::custom(width, height, format, p)
+        }
+    }
+}
+
+impl<P: Blendable+Sync+Send+'static> Frame<P> {
+    /// Start a task for each tile that writes it to `dst` once the pending
+    /// draws to the tile have finished, multisampled tiles are resolved
+    /// first. Returns the signals of the tasks, `dst` must outlive them.
+    unsafe fn write_tiles<W: Put<P>+Send>(&mut self, dst: *mut W) -> Vec<Signal> {
+        fn write<P: Planes, W: Put<P>>(t: &TileGroup<P>, x: u32, y: u32, dst: &mut W) {
+            if t.samples() > 1 {
+                t.resolve().write(x, y, dst);
+            } else {
+                t.write(x, y, dst);
+            }
+        }
+
+        self.read_tiles(dst, write::<P, W>)
+    }
+
+    /// Write every pixel of the frame to `dst`, this waits for all
+    /// of the pending draws to finish.
+    fn write_to<W: Put<P>+Send>(&mut self, dst: W) -> W {
+        let mut buffer = dst;
+        let signals = unsafe { self.write_tiles(&mut buffer) };
+        wait_all(&signals);
+        buffer
+    }
+
+    /// Copy the frame into a texture with a full mip chain
+    ///
+    /// Panics if the frame is empty.
+    pub fn to_texture(&mut self) -> Texture2D<P> {
+        self.check_texture();
+        let texels = Texels::new(self.width, self.height);
+        self.write_to(texels).into_texture()
+    }
+
+    /// Copy the frame into a texture once all of the pending draws have
+    /// finished, without waiting for them. Use `Frame::bind` to make the
+    /// draws to another frame wait for the texture.
+    ///
+    /// Panics if the frame is empty.
+    pub fn texture(&mut self) -> FrameTexture<P> {
+        use std::mem;
+        self.check_texture();
+        let slot = Arc::new(TextureSlot {
+            texels: UnsafeCell::new(Texels::new(self.width, self.height)),
+            texture: UnsafeCell::new(None),
+            ready: AtomicBool::new(false)
+        });
+
+        let signals = unsafe { self.write_tiles(slot.texels.get()) };
+        let done = slot.clone();
+        let mut build = task(move |_| unsafe {
+            let texels = mem::replace(&mut *done.texels.get(), Texels::new(0, 0));
+            *done.texture.get() = Some(texels.into_texture());
+            done.ready.store(true, Ordering::Release);
+        });
+        for signal in signals {
+            build = build.after(signal);
+        }
+
+        FrameTexture {
+            slot: slot,
+            signal: build.start(&mut self.pool)
+        }
+    }
+
+    /// Panic if the frame has no pixels to make a texture of, before
+    /// any of the work is handed to the pool
+    fn check_texture(&self) {
+        if self.width == 0 || self.height == 0 {
+            panic!("a texture needs at least one pixel, the frame is {}x{}", self.width, self.height);
+        }
+    }
+}
+
+/// The texels of a frame and the texture they become, shared by
+/// the tasks that build a `FrameTexture`.
+struct TextureSlot<P> {
+    texels: UnsafeCell<Texels<P>>,
+    texture: UnsafeCell<Option<Texture2D<P>>>,
+    /// set once `texture` is written, it is never written again after that
+    ready: AtomicBool
+}
+
+unsafe impl<P: Send> Send for TextureSlot<P> {}
+unsafe impl<P: Send + Sync> Sync for TextureSlot<P> {}
+
+/// A texture made from the pixels of a `Frame` by `Frame::texture`,
+/// it is filled in by a task once the frame is finished.
+pub struct FrameTexture<P> {
+    slot: Arc<TextureSlot<P>>,
+    signal: Signal
+}
+
+impl<P> Clone for FrameTexture<P> {
+    fn clone(&self) -> FrameTexture<P> {
+        FrameTexture {
+            slot: self.slot.clone(),
+            signal: self.signal.clone()
+        }
+    }
+}
+
+impl<P> FrameTexture<P> {
+    /// The signal that is pulsed once the texture is ready
+    pub fn signal(&self) -> Signal { self.signal.clone() }
 
-        Barrier::new(&signals).wait().unwrap();
-        unsafe { buffer.into_inner() }
+    /// The texture, or `None` if it is not built yet. It is always ready
+    /// in the fragment shaders of a frame the texture was bound to with
+    /// `Frame::bind`, and after `wait`.
+    pub fn get(&self) -> Option<&Texture2D<P>> {
+        if self.slot.ready.load(Ordering::Acquire) {
+            unsafe { (*self.slot.texture.get()).as_ref() }
+        } else {
+            None
+        }
+    }
+
+    /// Block until the texture is ready
+    pub fn wait(&self) -> &Texture2D<P> {
+        self.signal().wait().unwrap();
+        self.get().expect("the frame texture was not built")
+    }
+}
+
+impl Frame<Rgba<u8>> {
+    pub fn into_image(&mut self, img: ImageBuffer<Rgba<u8>, Vec<u8>>) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        self.write_to(img)
     }
 
     pub fn to_image(&mut self) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
@@ -0,0 +1,237 @@
+use cgmath::*;
+use genmesh::Triangle;
+
+use depth::DepthRange;
+use clip::Plane;
+
+/// A corner of the quad that a line is expanded into
+#[derive(Clone, Copy, Debug)]
+pub struct Corner {
+    /// the position in normalized device coordinates
+    pub pos: Vector3<f32>,
+    pub inv_w: f32,
+    /// the weights of the corner relative to the source triangle
+    pub weight: [f32; 3]
+}
+
+/// The per pixel coverage of an anti-aliased line, everything is
+/// measured in pixels.
+#[derive(Clone, Copy, Debug)]
+pub struct LineCoverage {
+    origin: Vector2<f32>,
+    dir: Vector2<f32>,
+    normal: Vector2<f32>,
+    length: f32,
+    half_width: f32
+}
+
+impl LineCoverage {
+    /// The fraction of the pixel at `p` that is covered by the line,
+    /// `p` is in normalized device coordinates.
+    #[inline]
+    pub fn coverage(&self, p: Vector2<f32>, scale: Vector2<f32>) -> f32 {
+        #[inline]
+        fn saturate(v: f32) -> f32 { v.max(0.).min(1.) }
+
+        let p = Vector2::new((p.x + 1.) / scale.x - self.origin.x,
+                             (p.y + 1.) / scale.y - self.origin.y);
+        let across = p.x * self.normal.x + p.y * self.normal.y;
+        let along = p.x * self.dir.x + p.y * self.dir.y;
+
+        saturate(self.half_width + 0.5 - across.abs()) *
+        saturate(along + 0.5) *
+        saturate(self.length - along + 0.5)
+    }
+}
+
+/// A one pixel wide aliased line, drawn with the diamond-exit rule. A
+/// pixel is drawn if the line leaves the diamond `|x| + |y| < 0.5` around
+/// its center, so the pixel holding the end point is left to the next line
+/// of a strip. Everything is measured in pixels.
+#[derive(Clone, Copy, Debug)]
+pub struct LineDiamond {
+    a: Vector2<f32>,
+    b: Vector2<f32>,
+    /// which of the tips of the diamond belong to it, for lines that
+    /// pass exactly between two pixels. x-major lines keep the top tip
+    /// and y-major lines the right one.
+    x_major: bool
+}
+
+impl LineDiamond {
+    /// Is `u`, a rotated coordinate of a point relative to a pixel center,
+    /// inside of the diamond. The upper bound is only included if `hi` is set.
+    #[inline]
+    fn inside(u: f32, hi: bool) -> bool {
+        if hi { u > -0.5 && u <= 0.5 } else { u >= -0.5 && u < 0.5 }
+    }
+
+    /// Does the line leave the diamond of the pixel at `p`
+    #[inline]
+    fn exits(&self, p: Vector2<f32>) -> bool {
+        // rotated by 45 degrees the diamond is the square |u|, |v| <= 0.5
+        let rotate = |q: Vector2<f32>| {
+            let (x, y) = (q.x - p.x, q.y - p.y);
+            [x + y, x - y]
+        };
+        let (a, b) = (rotate(self.a), rotate(self.b));
+        let hi = [true, !self.x_major];
+
+        if LineDiamond::inside(b[0], hi[0]) && LineDiamond::inside(b[1], hi[1]) {
+            return false;
+        }
+
+        let (mut t0, mut t1) = (0f32, 1f32);
+        for k in 0..2 {
+            let d = b[k] - a[k];
+            if d == 0. {
+                if !LineDiamond::inside(a[k], hi[k]) {
+                    return false;
+                }
+            } else {
+                let (ta, tb) = ((-0.5 - a[k]) / d, (0.5 - a[k]) / d);
+                t0 = t0.max(ta.min(tb));
+                t1 = t1.min(ta.max(tb));
+            }
+        }
+
+        if t0 < t1 {
+            true
+        } else if t0 > t1 {
+            false
+        } else {
+            // the line only touches the edge of the diamond
+            let u = [a[0] + (b[0] - a[0]) * t0, a[1] + (b[1] - a[1]) * t0];
+            LineDiamond::inside(u[0], hi[0]) && LineDiamond::inside(u[1], hi[1])
+        }
+    }
+
+    /// The pixels of the 8x8 tile at `pos` that the line is drawn to
+    #[inline]
+    pub fn mask(&self, pos: Vector2<f32>, scale: Vector2<f32>) -> u64 {
+        let mut mask = 0;
+        for i in 0..64 {
+            let p = pos + vec2((i & 7) as f32 * scale.x, (i >> 3) as f32 * scale.y);
+            if self.exits(Vector2::new((p.x + 1.) / scale.x, (p.y + 1.) / scale.y)) {
+                mask |= 1 << i;
+            }
+        }
+        mask
+    }
+}
+
+/// Clip a line against the near plane of `range`, the far plane and the
+/// guard band. Returns the parameters of the visible part of the line, or
+/// `None` if none of it is visible.
+pub fn clip_line(a: Vector4<f32>, b: Vector4<f32>, range: DepthRange) -> Option<(f32, f32)> {
+    let (mut t0, mut t1) = (0f32, 1f32);
+    let near = (range.near_distance(&a), range.near_distance(&b));
+    let planes = [near,
+                  (Plane::Far.distance(&a), Plane::Far.distance(&b)),
+                  (Plane::Left.distance(&a), Plane::Left.distance(&b)),
+                  (Plane::Right.distance(&a), Plane::Right.distance(&b)),
+                  (Plane::Bottom.distance(&a), Plane::Bottom.distance(&b)),
+                  (Plane::Top.distance(&a), Plane::Top.distance(&b))];
+
+    for &(da, db) in planes.iter() {
+        if da < 0. && db < 0. {
+            return None;
+        } else if da < 0. {
+            t0 = t0.max(da / (da - db));
+        } else if db < 0. {
+            t1 = t1.min(da / (da - db));
+        }
+    }
+
+    if t0 > t1 { None } else { Some((t0, t1)) }
+}
+
+/// Clip and project the line from `a` to `b` and expand it into a quad
+/// `width` pixels wide, `scale` is the size of a pixel. `wa` and `wb` are
+/// the weights of the end points relative to the source triangle.
+///
+/// Aliased lines that are one pixel wide are drawn with the diamond-exit
+/// rule, the quad only bounds the pixels that `LineDiamond` picks.
+/// Wider aliased lines are expanded along their minor axis, so an x-major
+/// line covers exactly `width` pixels in every column it crosses. Their
+/// ends are not extended, combined with the top-left fill rule lines that
+/// share an end point never draw the same pixel twice. Smooth lines are
+/// expanded by an extra half pixel on each side and come with the coverage
+/// that is used to fade out their edges.
+pub fn expand(a: Vector4<f32>,
+              b: Vector4<f32>,
+              wa: [f32; 3],
+              wb: [f32; 3],
+              width: f32,
+              smooth: bool,
+              range: DepthRange,
+              scale: Vector2<f32>) -> Option<([Triangle<Corner>; 2], Option<LineCoverage>, Option<LineDiamond>)> {
+
+    let (t0, t1) = match clip_line(a, b, range) {
+        Some(t) => t,
+        None => return None
+    };
+
+    let (ca, cb) = (a + (b - a).mul_s(t0), a + (b - a).mul_s(t1));
+    let lerp = |t: f32| [wa[0] + (wb[0] - wa[0]) * t,
+                         wa[1] + (wb[1] - wa[1]) * t,
+                         wa[2] + (wb[2] - wa[2]) * t];
+    let (wa, wb) = (lerp(t0), lerp(t1));
+    let (pa, pb) = (ca.truncate().div_s(ca.w), cb.truncate().div_s(cb.w));
+    let (ia, ib) = (ca.w.recip(), cb.w.recip());
+
+    let d = Vector2::new((pb.x - pa.x) / scale.x, (pb.y - pa.y) / scale.y);
+    let length = (d.x * d.x + d.y * d.y).sqrt();
+    if length == 0. || length.is_nan() {
+        return None;
+    }
+    let dir = d.div_s(length);
+    let normal = Vector2::new(-dir.y, dir.x);
+
+    let origin = Vector2::new((pa.x + 1.) / scale.x, (pa.y + 1.) / scale.y);
+    let x_major = dir.x.abs() >= dir.y.abs();
+
+    // the offset of the sides in pixels, and of the ends as a fraction of the line
+    let (side, cap, coverage, diamond) = if smooth {
+        let coverage = LineCoverage {
+            origin: origin,
+            dir: dir,
+            normal: normal,
+            length: length,
+            half_width: width * 0.5
+        };
+        (normal.mul_s(width * 0.5 + 0.5), 0.5 / length, Some(coverage), None)
+    } else if width <= 1. {
+        // the diamonds reach half a pixel past the line, the quad is a
+        // pixel wider on every side so that their tips are always inside
+        let diamond = LineDiamond {
+            a: origin,
+            b: origin + d,
+            x_major: x_major
+        };
+        let side = if x_major { Vector2::new(0., 1.) } else { Vector2::new(1., 0.) };
+        (side, 1. / d.x.abs().max(d.y.abs()), None, Some(diamond))
+    } else if x_major {
+        (Vector2::new(0., width * 0.5), 0., None, None)
+    } else {
+        (Vector2::new(width * 0.5, 0.), 0., None, None)
+    };
+
+    let corner = |s: f32, sign: f32| {
+        // 1/w is linear in screen space, the weights are not
+        let inv_w = ia + (ib - ia) * s;
+        let u = s * ib / inv_w;
+        Corner {
+            pos: Vector3::new(pa.x + (pb.x - pa.x) * s + side.x * sign * scale.x,
+                              pa.y + (pb.y - pa.y) * s + side.y * sign * scale.y,
+                              pa.z + (pb.z - pa.z) * s),
+            inv_w: inv_w,
+            weight: [wa[0] + (wb[0] - wa[0]) * u,
+                     wa[1] + (wb[1] - wa[1]) * u,
+                     wa[2] + (wb[2] - wa[2]) * u]
+        }
+    };
+
+    let q = [corner(-cap, -1.), corner(1. + cap, -1.), corner(1. + cap, 1.), corner(-cap, 1.)];
+    Some(([Triangle::new(q[0], q[1], q[2]), Triangle::new(q[0], q[2], q[3])], coverage, diamond))
+}
@@ -7,9 +7,75 @@ pub trait Fragment<T> {
     fn blend(&self, _: Self::Color, new: Self::Color) -> Self::Color { new }
 }
 
+/// A fragment shader that knows how each fragment was rasterized, see
+/// `FragmentContext`, and that can discard fragments. Every `Fragment`
+/// is one that ignores the context and discards nothing.
+pub trait ContextFragment<T> {
+    type Color;
+
+    /// Returning `None` discards the fragment and leaves the colour,
+    /// depth and stencil of the pixel untouched.
+    fn fragment_with(&self, pos: T, ctx: &FragmentContext<T>) -> Option<Self::Color>;
+
+    fn blend(&self, _: Self::Color, new: Self::Color) -> Self::Color { new }
+}
+
+impl<T, F> ContextFragment<T> for F where F: Fragment<T> {
+    type Color = F::Color;
+
+    #[inline]
+    fn fragment_with(&self, pos: T, _: &FragmentContext<T>) -> Option<F::Color> {
+        Some(self.fragment(pos))
+    }
+
+    #[inline]
+    fn blend(&self, dst: F::Color, new: F::Color) -> F::Color { Fragment::blend(self, dst, new) }
+}
+
+/// What is known about a fragment besides its attributes
+pub struct FragmentContext<'a, T: 'a> {
+    /// is the front of the triangle visible, always set for lines and points
+    pub front: bool,
+    /// the position of the fragment inside of a point sprite from `(0, 0)`
+    /// to `(1, 1)`, `None` for triangles and lines
+    pub point: Option<[f32; 2]>,
+    /// the screen space derivatives of the attributes, for texture LOD
+    /// selection or procedural anti-aliasing
+    pub derivatives: &'a Derivatives<T>
+}
+
+/// The screen space derivatives of the attributes of a fragment. Fragments
+/// are shaded in 2x2 quads and the derivatives are the differences across
+/// the quad, pixels of the quad that are outside of the triangle still
+/// take part. Attributes that are not interpolated, like `Flat`, have no
+/// derivative and return their value.
+pub trait Derivatives<T> {
+    /// The change of the attributes from the left to the right column of the quad
+    fn ddx(&self) -> T;
+    /// The change of the attributes from the bottom to the top row of the quad
+    fn ddy(&self) -> T;
+}
+
+/// A fragment shader that shades a whole 8x8 tile at once, used by
+/// `Frame::raster_wide`. Every attribute has one `f32x8x8` lane per pixel
+/// (see `InterpolateWide`), pixel `i` of the tile is at `x = i & 7`, `y = i >> 3`.
+pub trait WideFragment<T> {
+    type Color;
+
+    /// Shade the pixels in `mask` and write their colours to `color`,
+    /// the other pixels are ignored. Returns the pixels that were not
+    /// discarded.
+    fn fragment_wide(&self, pos: T, mask: u64, color: &mut [Self::Color; 64]) -> u64;
+
+    fn blend(&self, _: Self::Color, new: Self::Color) -> Self::Color { new }
+}
+
+/// A vertex shader used by `Frame::draw`. It returns the clip space
+/// position of the vertex, and the attributes that are interpolated
+/// for the fragment shader.
 pub trait Vertex<T> {
     type Out;
-    fn vertex(&self, v: T) -> Self::Out;
+    fn vertex(&self, v: &T) -> ([f32; 4], Self::Out);
 }
 
 pub trait Mapping<T> {
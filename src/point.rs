@@ -0,0 +1,100 @@
+use cgmath::*;
+use genmesh::Triangle;
+
+use line::Corner;
+use depth::DepthRange;
+
+/// A single vertex drawn as a screen aligned square
+#[derive(Clone, Copy, Debug)]
+pub struct Point<T> {
+    pub vertex: T,
+    /// the width and height of the square in pixels
+    pub size: f32
+}
+
+impl<T> Point<T> {
+    pub fn new(vertex: T, size: f32) -> Point<T> {
+        Point {
+            vertex: vertex,
+            size: size
+        }
+    }
+}
+
+/// The square a point was expanded into, measured in pixels
+#[derive(Clone, Copy, Debug)]
+pub struct PointSprite {
+    center: Vector2<f32>,
+    size: f32,
+    round: bool
+}
+
+impl PointSprite {
+    /// The point coordinate of the pixel at `p`, it runs from `(0, 0)` at
+    /// the lower left corner of the sprite to `(1, 1)` at the upper right.
+    #[inline]
+    pub fn coord(&self, p: Vector2<f32>, scale: Vector2<f32>) -> [f32; 2] {
+        [((p.x + 1.) / scale.x - self.center.x) / self.size + 0.5,
+         ((p.y + 1.) / scale.y - self.center.y) / self.size + 0.5]
+    }
+
+    /// The pixels of the 8x8 tile at `pos` that are not discarded, this
+    /// is every pixel unless the point is round.
+    #[inline]
+    pub fn mask(&self, pos: Vector2<f32>, scale: Vector2<f32>) -> u64 {
+        if !self.round {
+            return !0;
+        }
+
+        let mut mask = 0;
+        for i in 0..64 {
+            let p = pos + vec2((i & 7) as f32 * scale.x, (i >> 3) as f32 * scale.y);
+            let [u, v] = self.coord(p, scale);
+            let (u, v) = (u - 0.5, v - 0.5);
+            if u * u + v * v <= 0.25 {
+                mask |= 1 << i;
+            }
+        }
+        mask
+    }
+}
+
+/// Project a point and expand it into a square `size` pixels wide, `scale`
+/// is the size of a pixel and `weight` the weight of the point relative to
+/// the source triangle. Points are only clipped by their center, so a
+/// point that is partially off screen is still drawn.
+pub fn expand(pos: Vector4<f32>,
+              weight: [f32; 3],
+              size: f32,
+              round: bool,
+              range: DepthRange,
+              scale: Vector2<f32>) -> Option<([Triangle<Corner>; 2], PointSprite)> {
+
+    if range.near_distance(&pos) < 0. || pos.w - pos.z < 0. || !(size > 0.) {
+        return None;
+    }
+
+    let c = pos.truncate().div_s(pos.w);
+    let (hx, hy) = (size * 0.5 * scale.x, size * 0.5 * scale.y);
+
+    // points that can not touch the frame are dropped, like the guard
+    // band of a triangle this keeps the edge functions in range
+    if !(c.x.abs() - hx <= 1.) || !(c.y.abs() - hy <= 1.) {
+        return None;
+    }
+    let corner = |x: f32, y: f32| {
+        Corner {
+            pos: Vector3::new(c.x + x * hx, c.y + y * hy, c.z),
+            inv_w: pos.w.recip(),
+            weight: weight
+        }
+    };
+
+    let q = [corner(-1., -1.), corner(1., -1.), corner(1., 1.), corner(-1., 1.)];
+    let sprite = PointSprite {
+        center: Vector2::new((c.x + 1.) / scale.x, (c.y + 1.) / scale.y),
+        size: size,
+        round: round
+    };
+    Some(([Triangle::new(q[0], q[1], q[2]), Triangle::new(q[0], q[2], q[3])], sprite))
+}
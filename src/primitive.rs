@@ -0,0 +1,224 @@
+use genmesh::Triangle;
+
+/// How a list of indices or vertices is assembled into triangles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Topology {
+    /// every three indices form an independent triangle
+    TriangleList,
+    /// every index after the first two forms a triangle with the
+    /// previous two indices
+    TriangleStrip,
+    /// every index after the first two forms a triangle with the
+    /// previous index and the first index
+    TriangleFan
+}
+
+/// The vertex of a triangle that supplies the value of `Flat` attributes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProvokingVertex {
+    First,
+    Last
+}
+
+/// Primitive assembly settings. The assembled triangles are rotated so
+/// that the provoking vertex is always the first vertex of the triangle,
+/// this keeps the winding order intact.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Assembly {
+    pub topology: Topology,
+    /// an index that ends the current strip or fan and starts a new one,
+    /// only used when assembling from indices
+    pub restart: Option<u32>,
+    pub provoking: ProvokingVertex
+}
+
+impl Assembly {
+    pub fn new(topology: Topology) -> Assembly {
+        Assembly {
+            topology: topology,
+            restart: None,
+            provoking: ProvokingVertex::First
+        }
+    }
+
+    /// Assemble triangles of indices from an index buffer
+    pub fn indices<'a>(&self, indices: &'a [u32]) -> Assemble<'a> {
+        Assemble {
+            indices: indices,
+            assembly: *self,
+            start: 0,
+            count: 0
+        }
+    }
+
+    /// Assemble triangles from a stream of vertices
+    pub fn vertices<I, T>(&self, vertices: I) -> AssembleVertices<I, T>
+        where I: Iterator<Item=T>, T: Clone {
+        AssembleVertices {
+            vertices: vertices,
+            assembly: *self,
+            first: None,
+            last: None,
+            count: 0
+        }
+    }
+
+    /// Put the n-th triangle of a primitive into winding order, with
+    /// the provoking vertex first.
+    #[inline]
+    fn orient<T>(&self, n: usize, a: T, b: T, c: T) -> Triangle<T> {
+        let (t, provoking) = match (self.topology, self.provoking) {
+            (Topology::TriangleList, ProvokingVertex::First) => (Triangle::new(a, b, c), 0),
+            (Topology::TriangleList, ProvokingVertex::Last) => (Triangle::new(a, b, c), 2),
+            // every other triangle in a strip is flipped to keep the winding consistent
+            (Topology::TriangleStrip, p) if n & 1 == 1 => {
+                (Triangle::new(b, a, c), if p == ProvokingVertex::First { 1 } else { 2 })
+            }
+            (Topology::TriangleStrip, p) => {
+                (Triangle::new(a, b, c), if p == ProvokingVertex::First { 0 } else { 2 })
+            }
+            (Topology::TriangleFan, ProvokingVertex::First) => (Triangle::new(a, b, c), 1),
+            (Topology::TriangleFan, ProvokingVertex::Last) => (Triangle::new(a, b, c), 2)
+        };
+
+        match provoking {
+            0 => t,
+            1 => Triangle::new(t.y, t.z, t.x),
+            _ => Triangle::new(t.z, t.x, t.y)
+        }
+    }
+}
+
+impl From<Topology> for Assembly {
+    fn from(topology: Topology) -> Assembly { Assembly::new(topology) }
+}
+
+/// An iterator over the triangles described by an index buffer
+pub struct Assemble<'a> {
+    indices: &'a [u32],
+    assembly: Assembly,
+    /// the first index of the current strip or fan
+    start: usize,
+    /// the number of triangles emitted since `start`
+    count: usize
+}
+
+impl<'a> Assemble<'a> {
+    /// Also yield the diagonal of each triangle, see `diagonals`
+    pub fn diagonals(self) -> Diagonals<'a> {
+        Diagonals(self)
+    }
+
+    #[inline]
+    fn next_with_diagonal(&mut self) -> Option<(Triangle<u32>, usize)> {
+        let idx = self.indices;
+
+        loop {
+            let (s, n) = (self.start, self.count);
+            let (a, b, c) = match self.assembly.topology {
+                Topology::TriangleList => (s + 3*n, s + 3*n + 1, s + 3*n + 2),
+                Topology::TriangleStrip => (s + n, s + n + 1, s + n + 2),
+                Topology::TriangleFan => (s, s + n + 1, s + n + 2)
+            };
+
+            if c >= idx.len() {
+                return None;
+            }
+
+            // start over after the last restart index in this triangle
+            if let Some(restart) = self.assembly.restart {
+                let first = match self.assembly.topology {
+                    Topology::TriangleList => a,
+                    _ if n == 0 => a,
+                    _ => c
+                };
+                if let Some(i) = (first..c+1).rev().find(|&i| idx[i] == restart) {
+                    self.start = i + 1;
+                    self.count = 0;
+                    continue;
+                }
+            }
+
+            self.count += 1;
+            // the diagonal is opposite of the middle vertex, which
+            // is tracked through the rotation
+            let t = self.assembly.orient(n, (idx[a], false), (idx[b], true), (idx[c], false));
+            let diagonal = if t.x.1 { 1 } else if t.y.1 { 2 } else { 0 };
+            return Some((Triangle::new(t.x.0, t.y.0, t.z.0), diagonal));
+        }
+    }
+}
+
+impl<'a> Iterator for Assemble<'a> {
+    type Item = Triangle<u32>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Triangle<u32>> {
+        self.next_with_diagonal().map(|(t, _)| t)
+    }
+}
+
+/// Assembled triangles along with their diagonal, the edge between the
+/// first and the last vertex in the order of the indices. Edge `i` runs
+/// from vertex `i` to vertex `(i + 1) % 3` of the rotated triangle.
+pub struct Diagonals<'a>(Assemble<'a>);
+
+impl<'a> Iterator for Diagonals<'a> {
+    type Item = (Triangle<u32>, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<(Triangle<u32>, usize)> {
+        self.0.next_with_diagonal()
+    }
+}
+
+/// An iterator over the triangles assembled from a stream of vertices
+pub struct AssembleVertices<I, T> {
+    vertices: I,
+    assembly: Assembly,
+    first: Option<T>,
+    last: Option<T>,
+    count: usize
+}
+
+impl<I, T> Iterator for AssembleVertices<I, T> where I: Iterator<Item=T>, T: Clone {
+    type Item = Triangle<T>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Triangle<T>> {
+        let n = self.count;
+        let (a, b) = match (self.first.take(), self.last.take()) {
+            (Some(a), Some(b)) => (a, b),
+            _ => {
+                match (self.vertices.next(), self.vertices.next()) {
+                    (Some(a), Some(b)) => (a, b),
+                    _ => return None
+                }
+            }
+        };
+        let c = match self.vertices.next() {
+            Some(c) => c,
+            None => return None
+        };
+
+        match self.assembly.topology {
+            Topology::TriangleList => (),
+            Topology::TriangleStrip => {
+                self.first = Some(b.clone());
+                self.last = Some(c.clone());
+            }
+            Topology::TriangleFan => {
+                self.first = Some(a.clone());
+                self.last = Some(c.clone());
+            }
+        }
+
+        self.count += 1;
+        Some(self.assembly.orient(n, a, b, c))
+    }
+}
+
+/// Assemble the indices into triangles
+pub fn assemble<'a>(indices: &'a [u32], topology: Topology) -> Assemble<'a> {
+    Assembly::new(topology).indices(indices)
+}
@@ -0,0 +1,214 @@
+use cgmath::*;
+use genmesh::Triangle;
+
+use blend::Blend;
+use stencil::Stencil;
+use depth::{DepthRange, DepthFormat};
+
+/// Which faces get thrown away before rasterization
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CullFace {
+    None,
+    Front,
+    Back
+}
+
+/// The winding order of a front facing triangle. Winding is
+/// measured in normalized device coordinates, with y pointing up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrontFace {
+    Cw,
+    Ccw
+}
+
+/// How triangles are drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PolygonMode {
+    /// fill the inside of the triangle
+    Fill,
+    /// draw the edges of the triangle as lines
+    Line,
+    /// draw the vertices of the triangle as points
+    Point
+}
+
+/// The comparison used by the depth test, the incoming depth
+/// is on the left hand side of the comparison.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthFunc {
+    Never,
+    Less,
+    LessEqual,
+    Equal,
+    Greater,
+    GreaterEqual,
+    NotEqual,
+    Always
+}
+
+impl DepthFunc {
+    /// Combine the results of a less-than and a greater-than comparison
+    /// into the mask of the pixels that pass this test.
+    #[inline]
+    pub fn mask(self, less: u64, greater: u64) -> u64 {
+        match self {
+            DepthFunc::Never => 0,
+            DepthFunc::Less => less,
+            DepthFunc::LessEqual => !greater,
+            DepthFunc::Equal => !(less | greater),
+            DepthFunc::Greater => greater,
+            DepthFunc::GreaterEqual => !less,
+            DepthFunc::NotEqual => less | greater,
+            DepthFunc::Always => !0
+        }
+    }
+}
+
+/// Fixed function state used by `Frame::raster_with`
+#[derive(Clone, Copy, Debug)]
+pub struct RasterState {
+    pub cull: CullFace,
+    pub front_face: FrontFace,
+    pub depth: DepthFunc,
+    pub depth_write: bool,
+    /// The depth that triangles are clipped to
+    pub depth_range: DepthRange,
+    /// Fixed function blending, if this is `None` `Fragment::blend` is
+    /// used. See `Frame::enable_fixed_function`.
+    pub blend: Option<Blend>,
+    /// Fixed function blending for each target of a frame whose pixels
+    /// are a tuple of colours, this overrides `blend`. The targets with
+    /// no blend state are replaced.
+    pub blend_targets: Option<[Option<Blend>; 4]>,
+    /// The stencil test is disabled if this is `None`, the frame
+    /// must have a stencil (see `FrameFormat::stencil`).
+    pub stencil: Option<Stencil>,
+    /// The width of lines in pixels
+    pub line_width: f32,
+    /// Anti-alias lines by scaling the alpha of each pixel by its
+    /// coverage, this needs blending to be visible.
+    pub line_smooth: bool,
+    /// Discard the pixels of a point sprite that are outside of its circle
+    pub point_round: bool,
+    /// The size of points in pixels, used by `PolygonMode::Point`
+    pub point_size: f32,
+    pub polygon_mode: PolygonMode,
+    /// Skip the edge from the last vertex of a triangle back to the first
+    /// in `PolygonMode::Line`. This is the diagonal of a quad that was split
+    /// by genmesh's `Triangulate`, so only the outline of the quad is drawn.
+    /// Indexed triangles keep the diagonal of their index order whichever
+    /// vertex is provoking, triangles passed to `raster` are taken as is.
+    pub hide_diagonals: bool
+}
+
+impl RasterState {
+    /// Counter-clockwise triangles are front facing, back faces are culled
+    /// and the closest fragment wins the depth test. There is no blending
+    /// and no stencil test. Lines are one pixel wide and aliased,
+    /// points are square and triangles are filled.
+    pub fn new() -> RasterState {
+        RasterState {
+            cull: CullFace::Back,
+            front_face: FrontFace::Ccw,
+            depth: DepthFunc::Less,
+            depth_write: true,
+            depth_range: DepthRange::NegativeOneToOne,
+            blend: None,
+            blend_targets: None,
+            stencil: None,
+            line_width: 1.,
+            line_smooth: false,
+            point_round: false,
+            point_size: 1.,
+            polygon_mode: PolygonMode::Fill,
+            hide_diagonals: false
+        }
+    }
+
+    /// Reversed-Z, depth runs from 1 at the near plane to 0 at the far
+    /// plane and the closest fragment has the greatest depth. Use this with
+    /// a projection from `reversed_z`, on a frame with `FrameFormat::reversed_z`.
+    pub fn reversed_z() -> RasterState {
+        RasterState {
+            depth: DepthFunc::Greater,
+            depth_range: DepthRange::ZeroToOne,
+            .. RasterState::new()
+        }
+    }
+
+    /// Does this state blend or smooth lines, these need the fixed
+    /// function operations of the colour of the frame.
+    #[inline]
+    pub fn fixed_function(&self) -> bool {
+        self.blend.is_some() || self.blend_targets.is_some() || self.line_smooth
+    }
+
+    /// Check which side of the triangle is visible. Returns `None` if the
+    /// triangle should be culled, otherwise `Some(true)` for front faces
+    /// and `Some(false)` for back faces. Degenerate triangles are always
+    /// culled since they can not cover any pixels.
+    #[inline]
+    pub fn facing(&self, t: &Triangle<Vector3<f32>>) -> Option<bool> {
+        let e0 = t.y - t.x;
+        let e1 = t.z - t.x;
+        let area = e0.x * e1.y - e0.y * e1.x;
+
+        if area == 0. || area.is_nan() {
+            return None;
+        }
+
+        let front = match self.front_face {
+            FrontFace::Ccw => area > 0.,
+            FrontFace::Cw => area < 0.
+        };
+
+        match (self.cull, front) {
+            (CullFace::Front, true) | (CullFace::Back, false) => None,
+            _ => Some(front)
+        }
+    }
+}
+
+impl Default for RasterState {
+    fn default() -> RasterState { RasterState::new() }
+}
+
+/// What a frame stores for every pixel besides its colour, used by
+/// `Frame::with_format`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameFormat {
+    /// The number of samples per pixel, 1, 2, 4 or 8
+    pub samples: usize,
+    /// Store a stencil value for every sample, this is needed by
+    /// `RasterState::stencil`.
+    pub stencil: bool,
+    /// How depth is stored, a `DepthFormat::Unorm24` frame packs the
+    /// stencil into the depth
+    pub depth: DepthFormat,
+    /// The far plane is at depth 0, a new frame and `Frame::clear`
+    /// start out with this depth instead of 1. See `RasterState::reversed_z`.
+    pub reversed_z: bool
+}
+
+impl FrameFormat {
+    /// One sample per pixel, no stencil and floating point depth
+    pub fn new() -> FrameFormat {
+        FrameFormat {
+            samples: 1,
+            stencil: false,
+            depth: DepthFormat::Float32,
+            reversed_z: false
+        }
+    }
+
+    /// The window depth of the far plane, what the depth of a
+    /// frame is cleared to
+    #[inline]
+    pub fn far(&self) -> f32 {
+        if self.reversed_z { 0. } else { 1. }
+    }
+}
+
+impl Default for FrameFormat {
+    fn default() -> FrameFormat { FrameFormat::new() }
+}
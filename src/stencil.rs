@@ -0,0 +1,128 @@
+use DepthFunc;
+
+/// What happens to the stencil value of a pixel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StencilOp {
+    Keep,
+    Zero,
+    Replace,
+    IncrementClamp,
+    DecrementClamp,
+    IncrementWrap,
+    DecrementWrap,
+    Invert
+}
+
+impl StencilOp {
+    #[inline]
+    fn apply(self, v: u8, reference: u8) -> u8 {
+        match self {
+            StencilOp::Keep => v,
+            StencilOp::Zero => 0,
+            StencilOp::Replace => reference,
+            StencilOp::IncrementClamp => v.saturating_add(1),
+            StencilOp::DecrementClamp => v.saturating_sub(1),
+            StencilOp::IncrementWrap => v.wrapping_add(1),
+            StencilOp::DecrementWrap => v.wrapping_sub(1),
+            StencilOp::Invert => !v
+        }
+    }
+}
+
+/// The stencil test and operations for one side of a triangle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StencilFace {
+    /// compares `reference & read_mask` against `stencil & read_mask`
+    pub func: DepthFunc,
+    pub reference: u8,
+    pub read_mask: u8,
+    pub write_mask: u8,
+    /// the stencil test failed
+    pub fail: StencilOp,
+    /// the stencil test passed but the depth test failed
+    pub depth_fail: StencilOp,
+    /// both the stencil and depth tests passed
+    pub pass: StencilOp
+}
+
+impl StencilFace {
+    /// A test that always passes and never modifies the stencil buffer
+    pub fn new() -> StencilFace {
+        StencilFace {
+            func: DepthFunc::Always,
+            reference: 0,
+            read_mask: 0xFF,
+            write_mask: 0xFF,
+            fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep
+        }
+    }
+
+    /// Returns the subset of `mask` that passes the stencil test
+    #[inline]
+    pub fn test(&self, plane: &[u8; 64], mut mask: u64) -> u64 {
+        let reference = self.reference & self.read_mask;
+        let mut out = 0;
+        while mask != 0 {
+            let i = mask.trailing_zeros();
+            mask &= !(1 << i);
+
+            let v = plane[i as usize] & self.read_mask;
+            let pass = match self.func {
+                DepthFunc::Never => false,
+                DepthFunc::Less => reference < v,
+                DepthFunc::LessEqual => reference <= v,
+                DepthFunc::Equal => reference == v,
+                DepthFunc::Greater => reference > v,
+                DepthFunc::GreaterEqual => reference >= v,
+                DepthFunc::NotEqual => reference != v,
+                DepthFunc::Always => true
+            };
+            if pass {
+                out |= 1 << i;
+            }
+        }
+        out
+    }
+
+    /// Apply `op` to every pixel in `mask`
+    #[inline]
+    pub fn update(&self, op: StencilOp, plane: &mut [u8; 64], mut mask: u64) {
+        if op == StencilOp::Keep || self.write_mask == 0 {
+            return;
+        }
+
+        while mask != 0 {
+            let i = mask.trailing_zeros();
+            mask &= !(1 << i);
+
+            let v = &mut plane[i as usize];
+            let new = op.apply(*v, self.reference);
+            *v = (*v & !self.write_mask) | (new & self.write_mask);
+        }
+    }
+}
+
+/// The stencil state, front and back facing triangles
+/// each have their own test and operations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stencil {
+    pub front: StencilFace,
+    pub back: StencilFace
+}
+
+impl Stencil {
+    /// Use the same test and operations for both faces
+    pub fn new(face: StencilFace) -> Stencil {
+        Stencil {
+            front: face,
+            back: face
+        }
+    }
+
+    #[inline]
+    pub fn face(&self, front: bool) -> &StencilFace {
+        if front { &self.front } else { &self.back }
+    }
+}
@@ -0,0 +1,721 @@
+use std::f32;
+
+use image::GenericImage;
+
+use tile::Put;
+use f32x8::f32x8x8;
+use Blendable;
+
+/// How a texture is filtered
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    /// the closest texel of the closest mip level
+    Nearest,
+    /// a blend of the four closest texels of the closest mip level
+    Bilinear,
+    /// a blend of the bilinear samples of the two closest mip levels
+    Trilinear
+}
+
+/// What happens to texture coordinates outside of `[0, 1]`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Wrap {
+    /// the texture is tiled
+    Repeat,
+    /// the texels on the edge of the texture are repeated
+    Clamp,
+    /// the texture is tiled, every other copy is flipped
+    Mirror
+}
+
+impl Wrap {
+    /// Wrap the texel coordinate `i` into `0..n`
+    #[inline]
+    pub fn apply(self, i: i32, n: i32) -> i32 {
+        match self {
+            Wrap::Repeat => ((i % n) + n) % n,
+            Wrap::Clamp => i.max(0).min(n - 1),
+            Wrap::Mirror => {
+                let i = ((i % (2 * n)) + 2 * n) % (2 * n);
+                if i < n { i } else { 2 * n - 1 - i }
+            }
+        }
+    }
+}
+
+#[inline]
+fn lerp(a: [f32; 4], b: [f32; 4], f: f32) -> [f32; 4] {
+    [a[0] + (b[0] - a[0]) * f,
+     a[1] + (b[1] - a[1]) * f,
+     a[2] + (b[2] - a[2]) * f,
+     a[3] + (b[3] - a[3]) * f]
+}
+
+/// Blend the bottom left, bottom right, top left and top right texels
+#[inline]
+fn bilerp(a: [f32; 4], b: [f32; 4], c: [f32; 4], d: [f32; 4], fx: f32, fy: f32) -> [f32; 4] {
+    lerp(lerp(a, b, fx), lerp(c, d, fx), fy)
+}
+
+/// Split a texel coordinate into the texel before it and the
+/// fraction of the way to the next texel
+#[inline]
+fn split(v: f32, size: u32) -> (i32, f32) {
+    let v = v * size as f32 - 0.5;
+    let v0 = v.floor();
+    (v0 as i32, v - v0)
+}
+
+/// A single level of a mip chain, stored a row at a time
+/// starting with the bottom row.
+#[derive(Clone, Debug)]
+pub struct Level<P> {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<P>
+}
+
+impl<P: Copy> Level<P> {
+    /// The texel at `x`, `y` after wrapping
+    #[inline]
+    pub fn texel(&self, x: i32, y: i32, wrap: [Wrap; 2]) -> P {
+        let x = wrap[0].apply(x, self.width as i32);
+        let y = wrap[1].apply(y, self.height as i32);
+        self.data[(y as u32 * self.width + x as u32) as usize]
+    }
+}
+
+/// The texels of a row of `size` texels that texel `i` of the next
+/// level is averaged from, and their weights. An odd row is reduced
+/// three texels at a time, weighted by how much of each texel the
+/// smaller texel covers, so every texel contributes equally.
+#[inline]
+fn taps(size: u32, i: u32) -> [(i32, f32); 3] {
+    let x = 2 * i as i32;
+    if size == 1 {
+        [(0, 1.), (0, 0.), (0, 0.)]
+    } else if size % 2 == 0 {
+        [(x, 0.5), (x + 1, 0.5), (x, 0.)]
+    } else {
+        let m = size / 2;
+        let n = (2 * m + 1) as f32;
+        [(x, (m - i) as f32 / n), (x + 1, m as f32 / n), (x + 2, (i + 1) as f32 / n)]
+    }
+}
+
+impl<P: Blendable> Level<P> {
+    /// Downsample the level to half its size rounded down, averaging
+    /// the texels that each texel of the smaller level covers. See `taps`
+    /// for the rows and columns of odd sized levels.
+    fn downsample(&self) -> Level<P> {
+        let (w, h) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        let mut data = Vec::with_capacity((w * h) as usize);
+        for y in 0..h {
+            for x in 0..w {
+                let mut sum = [0.; 4];
+                for &(ty, wy) in taps(self.height, y).iter() {
+                    for &(tx, wx) in taps(self.width, x).iter() {
+                        if wx * wy == 0. {
+                            continue;
+                        }
+                        let c = self.texel(tx, ty, [Wrap::Clamp, Wrap::Clamp]).to_rgba();
+                        for ch in 0..4 {
+                            sum[ch] += c[ch] * wx * wy;
+                        }
+                    }
+                }
+                data.push(Blendable::from_rgba(sum));
+            }
+        }
+
+        Level {
+            width: w,
+            height: h,
+            data: data
+        }
+    }
+
+    /// Filter the four texels closest to `uv`
+    #[inline]
+    fn bilinear(&self, uv: [f32; 2], wrap: [Wrap; 2]) -> [f32; 4] {
+        let (x0, fx) = split(uv[0], self.width);
+        let (y0, fy) = split(uv[1], self.height);
+
+        bilerp(self.texel(x0,     y0,     wrap).to_rgba(),
+               self.texel(x0 + 1, y0,     wrap).to_rgba(),
+               self.texel(x0,     y0 + 1, wrap).to_rgba(),
+               self.texel(x0 + 1, y0 + 1, wrap).to_rgba(), fx, fy)
+    }
+
+    /// The texel that contains `uv`
+    #[inline]
+    fn nearest(&self, uv: [f32; 2], wrap: [Wrap; 2]) -> P {
+        let x = (uv[0] * self.width as f32).floor() as i32;
+        let y = (uv[1] * self.height as f32).floor() as i32;
+        self.texel(x, y, wrap)
+    }
+}
+
+/// A two dimensional texture with a full mip chain. Texture coordinates
+/// run from `(0, 0)` at the bottom left to `(1, 1)` at the top right,
+/// the same orientation as the pixels of a `Frame`.
+#[derive(Clone, Debug)]
+pub struct Texture2D<P> {
+    levels: Vec<Level<P>>
+}
+
+impl<P: Blendable> Texture2D<P> {
+    /// Create a texture from `data`, stored a row at a time starting
+    /// with the bottom row. The mip chain is generated down to 1x1.
+    pub fn new(width: u32, height: u32, data: Vec<P>) -> Texture2D<P> {
+        assert!(width > 0 && height > 0);
+        assert!(data.len() == (width * height) as usize);
+
+        let mut levels = vec![Level {
+            width: width,
+            height: height,
+            data: data
+        }];
+        loop {
+            let next = {
+                let last = &levels[levels.len() - 1];
+                if last.width == 1 && last.height == 1 {
+                    break;
+                }
+                last.downsample()
+            };
+            levels.push(next);
+        }
+
+        Texture2D {
+            levels: levels
+        }
+    }
+
+    /// Create a texture from an image, the top row of the image
+    /// becomes the top row of the texture.
+    pub fn from_image<I>(img: &I) -> Texture2D<P> where I: GenericImage<Pixel=P> {
+        let (w, h) = img.dimensions();
+        let mut data = Vec::with_capacity((w * h) as usize);
+        for y in 0..h {
+            for x in 0..w {
+                data.push(img.get_pixel(x, h - 1 - y));
+            }
+        }
+        Texture2D::new(w, h, data)
+    }
+
+    #[inline] pub fn width(&self) -> u32 { self.levels[0].width }
+    #[inline] pub fn height(&self) -> u32 { self.levels[0].height }
+
+    /// The number of levels in the mip chain, including the texture itself
+    #[inline] pub fn levels(&self) -> usize { self.levels.len() }
+
+    /// A level of the mip chain, level 0 is the full sized texture
+    #[inline] pub fn level(&self, i: usize) -> &Level<P> { &self.levels[i] }
+
+    /// The level of detail of a pixel whose texture coordinates change by
+    /// `ddx` and `ddy` between it and its neighbours.
+    #[inline]
+    pub fn lod(&self, ddx: [f32; 2], ddy: [f32; 2]) -> f32 {
+        let (w, h) = (self.width() as f32, self.height() as f32);
+        let x = (ddx[0] * w) * (ddx[0] * w) + (ddx[1] * h) * (ddx[1] * h);
+        let y = (ddy[0] * w) * (ddy[0] * w) + (ddy[1] * h) * (ddy[1] * h);
+        0.5 * x.max(y).log2()
+    }
+}
+
+/// A stack of two dimensional textures of the same size, each layer
+/// is filtered on its own.
+#[derive(Clone, Debug)]
+pub struct Texture2DArray<P> {
+    layers: Vec<Texture2D<P>>
+}
+
+impl<P: Blendable> Texture2DArray<P> {
+    pub fn new(layers: Vec<Texture2D<P>>) -> Texture2DArray<P> {
+        assert!(layers.len() > 0);
+        assert!(layers.iter().all(|l| l.width() == layers[0].width() &&
+                                       l.height() == layers[0].height()));
+        Texture2DArray {
+            layers: layers
+        }
+    }
+
+    #[inline] pub fn layers(&self) -> usize { self.layers.len() }
+    #[inline] pub fn layer(&self, i: usize) -> &Texture2D<P> { &self.layers[i] }
+}
+
+/// The direction each face of a cube map looks in, followed by the
+/// directions of its u and v axes. This is a camera looking along the
+/// face with a 90 degree field of view, so a `Frame` rendered by that
+/// camera can be used as the face as is.
+static FACES: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+    ([ 1.,  0.,  0.], [ 0.,  0.,  1.], [ 0.,  1.,  0.]),
+    ([-1.,  0.,  0.], [ 0.,  0., -1.], [ 0.,  1.,  0.]),
+    ([ 0.,  1.,  0.], [ 1.,  0.,  0.], [ 0.,  0.,  1.]),
+    ([ 0., -1.,  0.], [ 1.,  0.,  0.], [ 0.,  0., -1.]),
+    ([ 0.,  0.,  1.], [-1.,  0.,  0.], [ 0.,  1.,  0.]),
+    ([ 0.,  0., -1.], [ 1.,  0.,  0.], [ 0.,  1.,  0.])
+];
+
+#[inline]
+fn dot3(a: [f32; 3], b: [f32; 3]) -> f32 { a[0] * b[0] + a[1] * b[1] + a[2] * b[2] }
+
+/// Six square textures that are sampled by direction. The faces are in
+/// the order +x, -x, +y, -y, +z, -z. Bilinear filtering near the edge
+/// of a face reads the texels of the neighbouring face, so there are
+/// no seams between the faces.
+#[derive(Clone, Debug)]
+pub struct TextureCube<P> {
+    faces: Vec<Texture2D<P>>
+}
+
+impl<P: Blendable> TextureCube<P> {
+    pub fn new(faces: Vec<Texture2D<P>>) -> TextureCube<P> {
+        assert!(faces.len() == 6);
+        assert!(faces.iter().all(|f| f.width() == faces[0].width() &&
+                                      f.height() == faces[0].width()));
+        TextureCube {
+            faces: faces
+        }
+    }
+
+    /// The direction and the up vector of the camera that renders `face`
+    #[inline]
+    pub fn view(face: usize) -> ([f32; 3], [f32; 3]) {
+        let (forward, _, up) = FACES[face];
+        (forward, up)
+    }
+
+    #[inline] pub fn size(&self) -> u32 { self.faces[0].width() }
+    #[inline] pub fn levels(&self) -> usize { self.faces[0].levels() }
+    #[inline] pub fn face(&self, i: usize) -> &Texture2D<P> { &self.faces[i] }
+
+    /// The face that `dir` points at, and where on that face it points
+    #[inline]
+    pub fn project(dir: [f32; 3]) -> (usize, [f32; 2]) {
+        let (x, y, z) = (dir[0].abs(), dir[1].abs(), dir[2].abs());
+        let face = if x >= y && x >= z {
+            if dir[0] >= 0. { 0 } else { 1 }
+        } else if y >= z {
+            if dir[1] >= 0. { 2 } else { 3 }
+        } else {
+            if dir[2] >= 0. { 4 } else { 5 }
+        };
+
+        let (forward, u, v) = FACES[face];
+        let major = dot3(dir, forward);
+        (face, [(dot3(dir, u) / major + 1.) * 0.5,
+                (dot3(dir, v) / major + 1.) * 0.5])
+    }
+
+    /// The texel at `x`, `y` of `face`, texels past the edge of the
+    /// face are read from the neighbouring face.
+    fn texel(&self, level: usize, face: usize, x: i32, y: i32) -> [f32; 4] {
+        let clamp = [Wrap::Clamp, Wrap::Clamp];
+        let l = self.faces[face].level(level);
+        let n = l.width as i32;
+        if x >= 0 && x < n && y >= 0 && y < n {
+            return l.texel(x, y, clamp).to_rgba();
+        }
+
+        let (forward, u, v) = FACES[face];
+        let s = (x as f32 + 0.5) / n as f32 * 2. - 1.;
+        let t = (y as f32 + 0.5) / n as f32 * 2. - 1.;
+        let dir = [forward[0] + u[0] * s + v[0] * t,
+                   forward[1] + u[1] * s + v[1] * t,
+                   forward[2] + u[2] * s + v[2] * t];
+
+        let (face, uv) = TextureCube::<P>::project(dir);
+        let x = (uv[0] * n as f32).floor() as i32;
+        let y = (uv[1] * n as f32).floor() as i32;
+        self.faces[face].level(level).texel(x, y, clamp).to_rgba()
+    }
+
+    fn filter(&self, level: usize, face: usize, uv: [f32; 2], linear: bool) -> [f32; 4] {
+        let n = self.faces[face].level(level).width;
+        if !linear {
+            let x = (uv[0] * n as f32).floor() as i32;
+            let y = (uv[1] * n as f32).floor() as i32;
+            let last = n as i32 - 1;
+            return self.texel(level, face, x.max(0).min(last), y.max(0).min(last));
+        }
+
+        let (x0, fx) = split(uv[0], n);
+        let (y0, fy) = split(uv[1], n);
+        bilerp(self.texel(level, face, x0,     y0),
+               self.texel(level, face, x0 + 1, y0),
+               self.texel(level, face, x0,     y0 + 1),
+               self.texel(level, face, x0 + 1, y0 + 1), fx, fy)
+    }
+}
+
+/// A single level of the mip chain of a `Texture3D`, stored a slice
+/// at a time. Each slice is stored like a `Level`.
+#[derive(Clone, Debug)]
+pub struct Volume<P> {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub data: Vec<P>
+}
+
+impl<P: Copy> Volume<P> {
+    /// The texel at `x`, `y`, `z` after wrapping
+    #[inline]
+    pub fn texel(&self, x: i32, y: i32, z: i32, wrap: [Wrap; 3]) -> P {
+        let x = wrap[0].apply(x, self.width as i32) as u32;
+        let y = wrap[1].apply(y, self.height as i32) as u32;
+        let z = wrap[2].apply(z, self.depth as i32) as u32;
+        self.data[((z * self.height + y) * self.width + x) as usize]
+    }
+}
+
+impl<P: Blendable> Volume<P> {
+    /// Downsample the volume to half its size rounded down, like
+    /// `Level::downsample` with a third axis.
+    fn downsample(&self) -> Volume<P> {
+        let clamp = [Wrap::Clamp, Wrap::Clamp, Wrap::Clamp];
+        let (w, h, d) = ((self.width / 2).max(1), (self.height / 2).max(1), (self.depth / 2).max(1));
+        let mut data = Vec::with_capacity((w * h * d) as usize);
+        for z in 0..d {
+            for y in 0..h {
+                for x in 0..w {
+                    let mut sum = [0.; 4];
+                    for &(tz, wz) in taps(self.depth, z).iter() {
+                        for &(ty, wy) in taps(self.height, y).iter() {
+                            for &(tx, wx) in taps(self.width, x).iter() {
+                                let weight = wx * wy * wz;
+                                if weight == 0. {
+                                    continue;
+                                }
+                                let c = self.texel(tx, ty, tz, clamp).to_rgba();
+                                for ch in 0..4 {
+                                    sum[ch] += c[ch] * weight;
+                                }
+                            }
+                        }
+                    }
+                    data.push(Blendable::from_rgba(sum));
+                }
+            }
+        }
+
+        Volume {
+            width: w,
+            height: h,
+            depth: d,
+            data: data
+        }
+    }
+
+    /// Filter the eight texels closest to `uvw`
+    #[inline]
+    fn trilinear(&self, uvw: [f32; 3], wrap: [Wrap; 3]) -> [f32; 4] {
+        let (x0, fx) = split(uvw[0], self.width);
+        let (y0, fy) = split(uvw[1], self.height);
+        let (z0, fz) = split(uvw[2], self.depth);
+
+        let slice = |z: i32| {
+            bilerp(self.texel(x0,     y0,     z, wrap).to_rgba(),
+                   self.texel(x0 + 1, y0,     z, wrap).to_rgba(),
+                   self.texel(x0,     y0 + 1, z, wrap).to_rgba(),
+                   self.texel(x0 + 1, y0 + 1, z, wrap).to_rgba(), fx, fy)
+        };
+        lerp(slice(z0), slice(z0 + 1), fz)
+    }
+
+    /// The texel that contains `uvw`
+    #[inline]
+    fn nearest(&self, uvw: [f32; 3], wrap: [Wrap; 3]) -> P {
+        self.texel((uvw[0] * self.width as f32).floor() as i32,
+                   (uvw[1] * self.height as f32).floor() as i32,
+                   (uvw[2] * self.depth as f32).floor() as i32, wrap)
+    }
+}
+
+/// A three dimensional texture with a full mip chain. The `w`
+/// coordinate runs from the first slice at 0 to the last at 1.
+#[derive(Clone, Debug)]
+pub struct Texture3D<P> {
+    levels: Vec<Volume<P>>
+}
+
+impl<P: Blendable> Texture3D<P> {
+    /// Create a texture from `data`, stored a slice at a time. Each
+    /// slice is stored like the data of a `Texture2D`.
+    pub fn new(width: u32, height: u32, depth: u32, data: Vec<P>) -> Texture3D<P> {
+        assert!(width > 0 && height > 0 && depth > 0);
+        assert!(data.len() == (width * height * depth) as usize);
+
+        let mut levels = vec![Volume {
+            width: width,
+            height: height,
+            depth: depth,
+            data: data
+        }];
+        loop {
+            let next = {
+                let last = &levels[levels.len() - 1];
+                if last.width == 1 && last.height == 1 && last.depth == 1 {
+                    break;
+                }
+                last.downsample()
+            };
+            levels.push(next);
+        }
+
+        Texture3D {
+            levels: levels
+        }
+    }
+
+    /// Stack the full sized levels of `slices` into a texture
+    pub fn from_slices(slices: &[Texture2D<P>]) -> Texture3D<P> {
+        assert!(slices.len() > 0);
+        let (w, h) = (slices[0].width(), slices[0].height());
+        let mut data = Vec::with_capacity((w * h) as usize * slices.len());
+        for s in slices.iter() {
+            assert!(s.width() == w && s.height() == h);
+            data.extend(s.level(0).data.iter().cloned());
+        }
+        Texture3D::new(w, h, slices.len() as u32, data)
+    }
+
+    #[inline] pub fn width(&self) -> u32 { self.levels[0].width }
+    #[inline] pub fn height(&self) -> u32 { self.levels[0].height }
+    #[inline] pub fn depth(&self) -> u32 { self.levels[0].depth }
+    #[inline] pub fn levels(&self) -> usize { self.levels.len() }
+    #[inline] pub fn level(&self, i: usize) -> &Volume<P> { &self.levels[i] }
+}
+
+/// How a texture is read by a fragment shader
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sampler {
+    pub filter: Filter,
+    /// the wrap mode of the u and v coordinates
+    pub wrap: [Wrap; 2],
+    /// the wrap mode of the w coordinate of a `Texture3D`
+    pub wrap_w: Wrap,
+    /// The most samples taken along the long axis of a stretched footprint,
+    /// 1 disables anisotropic filtering and at most 16 are taken.
+    pub max_anisotropy: u32,
+    /// added to the level of detail before it is clamped
+    pub lod_bias: f32,
+    /// the most detailed level that is sampled, after the bias
+    pub min_lod: f32,
+    /// the least detailed level that is sampled, after the bias
+    pub max_lod: f32
+}
+
+impl Sampler {
+    /// Trilinear filtering without anisotropy, with the texture repeated
+    /// in every direction. The level of detail is not biased or clamped.
+    pub fn new() -> Sampler {
+        Sampler {
+            filter: Filter::Trilinear,
+            wrap: [Wrap::Repeat, Wrap::Repeat],
+            wrap_w: Wrap::Repeat,
+            max_anisotropy: 1,
+            lod_bias: 0.,
+            min_lod: 0.,
+            max_lod: f32::MAX
+        }
+    }
+
+    /// Sample the texture at level of detail 0
+    #[inline]
+    pub fn sample<P: Blendable>(&self, texture: &Texture2D<P>, uv: [f32; 2]) -> P {
+        self.sample_lod(texture, uv, 0.)
+    }
+
+    /// Sample the texture with the footprint of the pixel taken from the
+    /// derivatives of the texture coordinates, see `FragmentContext::derivatives`.
+    ///
+    /// With anisotropic filtering a footprint that is longer than it is
+    /// wide is covered by several samples along its long axis, each at the
+    /// level of detail of the short axis.
+    pub fn sample_grad<P: Blendable>(&self, texture: &Texture2D<P>, uv: [f32; 2],
+                                     ddx: [f32; 2], ddy: [f32; 2]) -> P {
+        let (w, h) = (texture.width() as f32, texture.height() as f32);
+        let lx = ((ddx[0] * w) * (ddx[0] * w) + (ddx[1] * h) * (ddx[1] * h)).sqrt();
+        let ly = ((ddy[0] * w) * (ddy[0] * w) + (ddy[1] * h) * (ddy[1] * h)).sqrt();
+        let (axis, major, minor) = if lx >= ly { (ddx, lx, ly) } else { (ddy, ly, lx) };
+
+        let max = self.max_anisotropy.max(1).min(16) as f32;
+        // a footprint without width is as anisotropic as it gets
+        let n = if minor > 0. {
+            (major / minor).ceil().min(max)
+        } else if major > 0. {
+            max
+        } else {
+            1.
+        };
+        let lod = self.clamp_lod((major / n).log2());
+        if n <= 1. {
+            return Blendable::from_rgba(self.filtered(texture, uv, lod));
+        }
+
+        let mut sum = [0.; 4];
+        for i in 0..n as u32 {
+            let t = (i as f32 + 0.5) / n - 0.5;
+            let c = self.filtered(texture, [uv[0] + axis[0] * t, uv[1] + axis[1] * t], lod);
+            for ch in 0..4 {
+                sum[ch] += c[ch] / n;
+            }
+        }
+        Blendable::from_rgba(sum)
+    }
+
+    /// Sample the pixels of an 8x8 tile in `mask` and write them to `out`,
+    /// for use by a `WideFragment`. The footprint of each pixel is taken
+    /// from the texture coordinates of its neighbours in the tile.
+    pub fn sample_tile<P: Blendable>(&self, texture: &Texture2D<P>, uv: [f32x8x8; 2],
+                                     mask: u64, out: &mut [P; 64]) {
+        let (u, v) = (uv[0].to_array(), uv[1].to_array());
+
+        // the difference to the next pixel in the row or column, the
+        // last pixel uses the difference to the previous one instead
+        let diff = |i: usize, step: usize, last: bool| {
+            let (a, b) = if last { (i - step, i) } else { (i, i + step) };
+            [u[b] - u[a], v[b] - v[a]]
+        };
+
+        for i in (0..64).filter(|&i| mask & (1 << i) != 0) {
+            let ddx = diff(i, 1, i & 7 == 7);
+            let ddy = diff(i, 8, i >> 3 == 7);
+            out[i] = self.sample_grad(texture, [u[i], v[i]], ddx, ddy);
+        }
+    }
+
+    /// Sample the texture at the level of detail `lod`, level 0 is used
+    /// when the texture is magnified.
+    #[inline]
+    pub fn sample_lod<P: Blendable>(&self, texture: &Texture2D<P>, uv: [f32; 2], lod: f32) -> P {
+        Blendable::from_rgba(self.filtered(texture, uv, self.clamp_lod(lod)))
+    }
+
+    /// Apply the bias and clamps to a level of detail
+    #[inline]
+    fn clamp_lod(&self, lod: f32) -> f32 {
+        (lod + self.lod_bias).max(self.min_lod).min(self.max_lod)
+    }
+
+    /// Sample a layer of a texture array, `layer` is rounded to the closest layer
+    #[inline]
+    pub fn sample_array<P: Blendable>(&self, texture: &Texture2DArray<P>, uv: [f32; 2], layer: f32) -> P {
+        self.sample_array_lod(texture, uv, layer, 0.)
+    }
+
+    /// Sample a layer of a texture array at the level of detail `lod`,
+    /// every layer has its own mip chain
+    pub fn sample_array_lod<P: Blendable>(&self, texture: &Texture2DArray<P>,
+                                          uv: [f32; 2], layer: f32, lod: f32) -> P {
+        let last = (texture.layers() - 1) as f32;
+        let layer = texture.layer(layer.round().max(0.).min(last) as usize);
+        Blendable::from_rgba(self.filtered(layer, uv, self.clamp_lod(lod)))
+    }
+
+    /// Sample a cube map in the direction `dir`, it does not need to be normalized
+    #[inline]
+    pub fn sample_cube<P: Blendable>(&self, texture: &TextureCube<P>, dir: [f32; 3]) -> P {
+        self.sample_cube_lod(texture, dir, 0.)
+    }
+
+    /// Sample a cube map at the level of detail `lod`, the faces of a
+    /// level are filtered across their edges
+    pub fn sample_cube_lod<P: Blendable>(&self, texture: &TextureCube<P>, dir: [f32; 3], lod: f32) -> P {
+        let (face, uv) = TextureCube::<P>::project(dir);
+        Blendable::from_rgba(self.mip(texture.levels(), self.clamp_lod(lod), |level, linear| {
+            texture.filter(level, face, uv, linear)
+        }))
+    }
+
+    /// Sample a volume texture at level of detail 0
+    #[inline]
+    pub fn sample_3d<P: Blendable>(&self, texture: &Texture3D<P>, uvw: [f32; 3]) -> P {
+        self.sample_3d_lod(texture, uvw, 0.)
+    }
+
+    /// Sample a volume texture at the level of detail `lod`, linear
+    /// filters blend the eight closest texels of a level
+    pub fn sample_3d_lod<P: Blendable>(&self, texture: &Texture3D<P>, uvw: [f32; 3], lod: f32) -> P {
+        let wrap = [self.wrap[0], self.wrap[1], self.wrap_w];
+        Blendable::from_rgba(self.mip(texture.levels(), self.clamp_lod(lod), |level, linear| {
+            let level = texture.level(level);
+            if linear {
+                level.trilinear(uvw, wrap)
+            } else {
+                level.nearest(uvw, wrap).to_rgba()
+            }
+        }))
+    }
+
+    fn filtered<P: Blendable>(&self, texture: &Texture2D<P>, uv: [f32; 2], lod: f32) -> [f32; 4] {
+        self.mip(texture.levels(), lod, |level, linear| {
+            let level = texture.level(level);
+            if linear {
+                level.bilinear(uv, self.wrap)
+            } else {
+                level.nearest(uv, self.wrap).to_rgba()
+            }
+        })
+    }
+
+    /// Pick the mip levels for `lod` and blend them, `texel` filters
+    /// a single level either linearly or by taking the nearest texel.
+    #[inline]
+    fn mip<F>(&self, levels: usize, lod: f32, texel: F) -> [f32; 4] where F: Fn(usize, bool) -> [f32; 4] {
+        let lod = if lod > 0. { lod.min((levels - 1) as f32) } else { 0. };
+
+        match self.filter {
+            Filter::Nearest => texel(lod.round() as usize, false),
+            Filter::Bilinear => texel(lod.round() as usize, true),
+            Filter::Trilinear => {
+                let l0 = lod.floor();
+                let f = lod - l0;
+                let a = texel(l0 as usize, true);
+                if f == 0. {
+                    return a;
+                }
+                lerp(a, texel(l0 as usize + 1, true), f)
+            }
+        }
+    }
+}
+
+impl Default for Sampler {
+    fn default() -> Sampler { Sampler::new() }
+}
+
+/// The pixels of a `Frame` as they are read back a tile at a time
+pub struct Texels<P> {
+    width: u32,
+    height: u32,
+    data: Vec<Option<P>>
+}
+
+impl<P: Blendable> Texels<P> {
+    pub fn new(width: u32, height: u32) -> Texels<P> {
+        Texels {
+            width: width,
+            height: height,
+            data: vec![None; (width * height) as usize]
+        }
+    }
+
+    pub fn into_texture(self) -> Texture2D<P> {
+        let data = self.data.into_iter().map(|p| p.unwrap()).collect();
+        Texture2D::new(self.width, self.height, data)
+    }
+}
+
+impl<P: Copy> Put<P> for Texels<P> {
+    fn put(&mut self, x: u32, y: u32, p: P) {
+        // partial tiles on the edge of the frame extend past the texture
+        if x < self.width && y < self.height {
+            self.data[(y * self.width + x) as usize] = Some(p);
+        }
+    }
+}
@@ -5,8 +5,12 @@ use cgmath::*;
 use image::{Rgba, ImageBuffer};
 use genmesh::Triangle;
 
-use {Barycentric, Interpolate, Fragment, Mapping};
-use f32x8::{f32x8x8, f32x8x8_vec3};
+use {Barycentric, Edges, Interpolate, InterpolateWide, ContextFragment, FragmentContext, WideFragment, Derivatives, Mapping, RasterState, FrameFormat, DepthFunc, Planes, FixedFunction};
+use depth::{DepthFormat, DepthRange};
+use line::{LineCoverage, LineDiamond};
+use point::PointSprite;
+use stencil::StencilOp;
+use f32x8::{f32x8, f32x8x8, f32x8x8_vec2, f32x8x8_vec3};
 
 
 #[derive(Clone, Copy, Debug)]
@@ -18,14 +22,23 @@ pub struct TileMask {
 
 impl TileMask {
     #[inline(always)]
-    /// Calculate the u/v coordinates for the fragment
-    pub fn new(pos: Vector2<f32>, scale: Vector2<f32>, bary: &Barycentric) -> TileMask {
-        let [u, v] =  bary.coordinate_f32x8x8(pos, scale);
-        let uv = f32x8x8::broadcast(1.) - (u + v);
+    /// Calculate the u/v coordinates and the coverage for the fragment
+    pub fn new(pos: Vector2<f32>, scale: Vector2<f32>, setup: &Setup) -> TileMask {
+        let [u, v] =  setup.bary.coordinate_f32x8x8(pos, scale);
 
-        let mask = !(uv.to_bit_u32x8x8().bitmask() |
-                      u.to_bit_u32x8x8().bitmask() |
-                      v.to_bit_u32x8x8().bitmask());
+        // pixels past the right or top edge of the frame belong to
+        // partial tiles and must never be written
+        let [x, y] = f32x8x8_vec2::range(pos, scale).0;
+        let inside = (x - f32x8::broadcast(1.)).to_bit_u32x8x8().bitmask() &
+                     (y - f32x8::broadcast(1.)).to_bit_u32x8x8().bitmask();
+
+        let mut mask = setup.edges.mask(pos, scale) & inside;
+        if let Some(ref point) = setup.point {
+            mask &= point.mask(pos, scale);
+        }
+        if let Some(ref diamond) = setup.diamond {
+            mask &= diamond.mask(pos, scale);
+        }
 
         TileMask {
             u: u,
@@ -34,16 +47,27 @@ impl TileMask {
         }
     }
 
+    /// Remove the pixels that fail the depth test against the stored depth
+    /// of the first tile of `d` from the mask, returns the window depth of
+    /// every pixel in the tile.
     #[inline(always)]
-    pub fn mask_with_depth(&mut self, z: &Vector3<f32>, d: &mut f32x8x8) {
+    pub fn mask_with_depth(&mut self, z: &Vector3<f32>, d: &DepthSlice, func: DepthFunc, range: DepthRange) -> f32x8x8 {
         let z = f32x8x8_vec3::broadcast(Vector3::new(z.x, z.y, z.z));
         let uv = f32x8x8::broadcast(1.) - (self.u + self.v);
         let weights = f32x8x8_vec3([uv, self.u, self.v]);
         let depth = weights.dot(z);
+        self.mask &= !(depth - f32x8x8::broadcast(range.near())).to_bit_u32x8x8().bitmask();
 
-        self.mask &= (depth - *d).to_bit_u32x8x8().bitmask();
-        self.mask &= !(f32x8x8::broadcast(1.) + depth).to_bit_u32x8x8().bitmask();
-        d.replace(depth, self.mask);
+        let depth = range.window_tile(depth);
+        let (less, greater) = d.compare(depth);
+        self.mask &= func.mask(less, greater);
+        depth
+    }
+
+    /// The screen space weights of every pixel in the tile
+    #[inline]
+    pub fn weights(&self) -> [f32x8x8; 3] {
+        [f32x8x8::broadcast(1.) - (self.u + self.v), self.u, self.v]
     }
 
     #[inline]
@@ -102,26 +126,604 @@ impl Iterator for TileMaskIter {
 
 #[derive(Copy)]
 pub struct Tile<P> {
-    depth: f32x8x8,
     color: [P; 64],
 }
 
 impl<P: Copy> Clone for Tile<P> {
     fn clone(&self) -> Tile<P> {
         Tile {
-            depth: self.depth,
             color: self.color
         }
     }
 }
 
+/// The stencil values of the pixels of a tile. They are kept apart from
+/// the tiles and only stored by frames that were created with a stencil,
+/// unless the depth format packs them into the depth.
+#[derive(Copy)]
+pub struct StencilTile([u8; 64]);
+
+impl Clone for StencilTile {
+    fn clone(&self) -> StencilTile { StencilTile(self.0) }
+}
+
+/// The depth of the pixels of a tile as 16 bit unsigned normalized integers
+#[derive(Copy)]
+pub struct Depth16([u16; 64]);
+
+impl Clone for Depth16 {
+    fn clone(&self) -> Depth16 { Depth16(self.0) }
+}
+
+/// The depth of the pixels of a tile as 24 bit unsigned normalized integers
+/// in the upper bits of each word, the stencil is in the lower 8 bits
+#[derive(Copy)]
+pub struct Depth24Stencil8([u32; 64]);
+
+impl Clone for Depth24Stencil8 {
+    fn clone(&self) -> Depth24Stencil8 { Depth24Stencil8(self.0) }
+}
+
 impl<P: Copy> Tile<P> {
     pub fn new(p: P) -> Tile<P> {
-         Tile {
-            depth: f32x8x8::broadcast(1.),
+        Tile {
             color: [p; 64]
-        }       
+        }
+    }
+}
+
+/// The depth of every tile of every sample plane of a group stored in
+/// the `DepthFormat` of the frame, 16 tiles per plane in the order they
+/// are rastered. The depth is window depth from 0 to 1.
+#[derive(Clone)]
+enum DepthPlanes {
+    Unorm16(Vec<Depth16>),
+    Unorm24(Vec<Depth24Stencil8>),
+    Float32(Vec<f32x8x8>)
+}
+
+impl DepthPlanes {
+    /// `tiles` tiles of depth at the far plane of `format`
+    fn new(format: &FrameFormat, tiles: usize) -> DepthPlanes {
+        let mut planes = match format.depth {
+            DepthFormat::Unorm16 => DepthPlanes::Unorm16(vec![Depth16([0; 64]); tiles]),
+            DepthFormat::Unorm24 => DepthPlanes::Unorm24(vec![Depth24Stencil8([0; 64]); tiles]),
+            DepthFormat::Float32 => DepthPlanes::Float32(vec![f32x8x8::broadcast(0.); tiles])
+        };
+        planes.clear(format.far(), 0);
+        planes
+    }
+
+    /// Clear the depth to the window depth `depth`, a stencil that is
+    /// packed into the depth is cleared to `stencil`
+    fn clear(&mut self, depth: f32, stencil: u8) {
+        match *self {
+            DepthPlanes::Unorm16(ref mut d) => {
+                let v = DepthFormat::Unorm16.encode(depth) as u16;
+                for t in d.iter_mut() {
+                    t.0 = [v; 64];
+                }
+            }
+            DepthPlanes::Unorm24(ref mut d) => {
+                let v = (DepthFormat::Unorm24.encode(depth) << 8) | stencil as u32;
+                for t in d.iter_mut() {
+                    t.0 = [v; 64];
+                }
+            }
+            DepthPlanes::Float32(ref mut d) => {
+                for t in d.iter_mut() {
+                    *t = f32x8x8::broadcast(depth);
+                }
+            }
+        }
+    }
+
+    /// The depth of the tiles from `start` up to `end`
+    #[inline]
+    fn slice(&mut self, start: usize, end: usize) -> DepthSlice {
+        match *self {
+            DepthPlanes::Unorm16(ref mut d) => DepthSlice::Unorm16(&mut d[start..end]),
+            DepthPlanes::Unorm24(ref mut d) => DepthSlice::Unorm24(&mut d[start..end]),
+            DepthPlanes::Float32(ref mut d) => DepthSlice::Float32(&mut d[start..end])
+        }
+    }
+
+    /// The window depth of the pixels of tile `i`
+    fn read(&self, i: usize) -> [f32; 64] {
+        let mut out = [0.; 64];
+        match *self {
+            DepthPlanes::Unorm16(ref d) => {
+                for (o, &v) in out.iter_mut().zip(d[i].0.iter()) {
+                    *o = DepthFormat::Unorm16.decode(v as u32);
+                }
+            }
+            DepthPlanes::Unorm24(ref d) => {
+                for (o, &v) in out.iter_mut().zip(d[i].0.iter()) {
+                    *o = DepthFormat::Unorm24.decode(v >> 8);
+                }
+            }
+            DepthPlanes::Float32(ref d) => out = d[i].to_array()
+        }
+        out
+    }
+
+    /// The depth of the first sample plane
+    fn first_plane(&self) -> DepthPlanes {
+        match *self {
+            DepthPlanes::Unorm16(ref d) => DepthPlanes::Unorm16(d[..16].to_vec()),
+            DepthPlanes::Unorm24(ref d) => DepthPlanes::Unorm24(d[..16].to_vec()),
+            DepthPlanes::Float32(ref d) => DepthPlanes::Float32(d[..16].to_vec())
+        }
+    }
+}
+
+/// The depth of a run of tiles of a `DepthPlanes`
+pub enum DepthSlice<'a> {
+    Unorm16(&'a mut [Depth16]),
+    Unorm24(&'a mut [Depth24Stencil8]),
+    Float32(&'a mut [f32x8x8])
+}
+
+impl<'a> DepthSlice<'a> {
+    #[inline]
+    fn len(&self) -> usize {
+        match *self {
+            DepthSlice::Unorm16(ref d) => d.len(),
+            DepthSlice::Unorm24(ref d) => d.len(),
+            DepthSlice::Float32(ref d) => d.len()
+        }
+    }
+
+    #[inline]
+    fn split_at(self, n: usize) -> (DepthSlice<'a>, DepthSlice<'a>) {
+        match self {
+            DepthSlice::Unorm16(d) => {
+                let (a, b) = d.split_at_mut(n);
+                (DepthSlice::Unorm16(a), DepthSlice::Unorm16(b))
+            }
+            DepthSlice::Unorm24(d) => {
+                let (a, b) = d.split_at_mut(n);
+                (DepthSlice::Unorm24(a), DepthSlice::Unorm24(b))
+            }
+            DepthSlice::Float32(d) => {
+                let (a, b) = d.split_at_mut(n);
+                (DepthSlice::Float32(a), DepthSlice::Float32(b))
+            }
+        }
+    }
+
+    /// Compare the window depth `depth` with the stored depth of the first
+    /// tile in its format, returns the pixels where `depth` is less and the
+    /// pixels where it is greater.
+    #[inline]
+    fn compare(&self, depth: f32x8x8) -> (u64, u64) {
+        match *self {
+            DepthSlice::Unorm16(ref d) => compare_unorm(depth, DepthFormat::Unorm16, |i| d[0].0[i] as u32),
+            DepthSlice::Unorm24(ref d) => compare_unorm(depth, DepthFormat::Unorm24, |i| d[0].0[i] >> 8),
+            DepthSlice::Float32(ref d) => {
+                let d = d[0];
+                ((depth - d).to_bit_u32x8x8().bitmask(),
+                 (d - depth).to_bit_u32x8x8().bitmask())
+            }
+        }
+    }
+
+    /// Store the window depth `depth` of the pixels in `mask` in the first tile
+    #[inline]
+    fn write(&mut self, depth: f32x8x8, mask: u64) {
+        let z = depth.to_array();
+        match *self {
+            DepthSlice::Unorm16(ref mut d) => {
+                let mut m = mask;
+                while m != 0 {
+                    let i = m.trailing_zeros() as usize;
+                    m &= !(1 << i);
+                    d[0].0[i] = DepthFormat::Unorm16.encode(z[i]) as u16;
+                }
+            }
+            DepthSlice::Unorm24(ref mut d) => {
+                let mut m = mask;
+                while m != 0 {
+                    let i = m.trailing_zeros() as usize;
+                    m &= !(1 << i);
+                    let word = &mut d[0].0[i];
+                    *word = (DepthFormat::Unorm24.encode(z[i]) << 8) | (*word & 0xFF);
+                }
+            }
+            DepthSlice::Float32(ref mut d) => d[0].replace(depth, mask)
+        }
+    }
+}
+
+/// Compare the window depth `depth` rounded to `format` with the
+/// `stored` values of a tile
+#[inline]
+fn compare_unorm<F>(depth: f32x8x8, format: DepthFormat, stored: F) -> (u64, u64) where F: Fn(usize) -> u32 {
+    let depth = depth.to_array();
+    let (mut less, mut greater) = (0, 0);
+    for i in 0..64 {
+        let (d, s) = (format.encode(depth[i]), stored(i));
+        if d < s {
+            less |= 1 << i;
+        } else if d > s {
+            greater |= 1 << i;
+        }
+    }
+    (less, greater)
+}
+
+/// The depth and the stencil of a run of tiles. `stencil` is empty if
+/// the frame has no stencil, or if it is packed into the depth.
+pub struct DepthStencil<'a> {
+    depth: DepthSlice<'a>,
+    stencil: &'a mut [StencilTile]
+}
+
+impl<'a> DepthStencil<'a> {
+    /// Split into four runs of a quarter of the tiles, one for each
+    /// child of a `Quad`
+    #[inline]
+    fn quarters(self) -> (DepthStencil<'a>, DepthStencil<'a>, DepthStencil<'a>, DepthStencil<'a>) {
+        let DepthStencil { depth, stencil } = self;
+        let n = depth.len() / 4;
+        let (d0, rest) = depth.split_at(n);
+        let (d1, rest) = rest.split_at(n);
+        let (d2, d3) = rest.split_at(n);
+
+        // the stencil of each child is a quarter of ours, or empty
+        let n = stencil.len() / 4;
+        let (s0, rest) = stencil.split_at_mut(n);
+        let (s1, rest) = rest.split_at_mut(n);
+        let (s2, s3) = rest.split_at_mut(n);
+
+        (DepthStencil { depth: d0, stencil: s0 },
+         DepthStencil { depth: d1, stencil: s1 },
+         DepthStencil { depth: d2, stencil: s2 },
+         DepthStencil { depth: d3, stencil: s3 })
+    }
+
+    /// The depth and the stencil of tile `i`
+    #[inline]
+    fn tile(self, i: usize) -> DepthStencil<'a> {
+        let DepthStencil { depth, stencil } = self;
+        let (_, depth) = depth.split_at(i);
+        let (depth, _) = depth.split_at(1);
+        DepthStencil {
+            depth: depth,
+            stencil: stencil_at(stencil, i)
+        }
+    }
+
+    /// The stencil of the first tile, `None` if there is no stencil
+    #[inline]
+    fn stencil(&self) -> Option<[u8; 64]> {
+        match self.depth {
+            DepthSlice::Unorm24(ref d) => {
+                let mut s = [0; 64];
+                for (s, &word) in s.iter_mut().zip(d[0].0.iter()) {
+                    *s = word as u8;
+                }
+                Some(s)
+            }
+            _ => self.stencil.first().map(|s| s.0)
+        }
+    }
+
+    #[inline]
+    fn set_stencil(&mut self, s: [u8; 64]) {
+        match self.depth {
+            DepthSlice::Unorm24(ref mut d) => {
+                for (word, &s) in d[0].0.iter_mut().zip(s.iter()) {
+                    *word = (*word & !0xFF) | s as u32;
+                }
+            }
+            _ => {
+                if let Some(plane) = self.stencil.first_mut() {
+                    plane.0 = s;
+                }
+            }
+        }
+    }
+
+    /// Run the stencil and depth tests on the covered pixels of the first
+    /// tile. The mask is left with the pixels that have to be shaded, the
+    /// ones that passed both tests and the ones whose stencil is updated
+    /// although they failed. Nothing is written until `commit` is called,
+    /// so a discarded fragment changes neither the depth nor the stencil.
+    #[inline]
+    fn test(&self, mask: &mut TileMask, state: &RasterState, setup: &Setup) -> Tested {
+        let (z, front) = (&setup.z, setup.front);
+        match (state.stencil, self.stencil()) {
+            (Some(ref stencil), Some(plane)) => {
+                let face = stencil.face(front);
+                let covered = mask.mask;
+                mask.mask = face.test(&plane, covered);
+                let stencil_fail = covered & !mask.mask;
+
+                let tested = mask.mask;
+                let depth = mask.mask_with_depth(z, &self.depth, state.depth, state.depth_range);
+                let depth_fail = tested & !mask.mask;
+
+                let passed = mask.mask;
+                if face.fail != StencilOp::Keep {
+                    mask.mask |= stencil_fail;
+                }
+                if face.depth_fail != StencilOp::Keep {
+                    mask.mask |= depth_fail;
+                }
+                Tested {
+                    depth: depth,
+                    passed: passed,
+                    stencil_fail: stencil_fail,
+                    depth_fail: depth_fail
+                }
+            }
+            _ => {
+                let depth = mask.mask_with_depth(z, &self.depth, state.depth, state.depth_range);
+                Tested {
+                    depth: depth,
+                    passed: mask.mask,
+                    stencil_fail: 0,
+                    depth_fail: 0
+                }
+            }
+        }
+    }
+
+    /// Write the depth and the stencil of the pixels that `test` checked,
+    /// `kept` are the pixels that were not discarded by the fragment.
+    #[inline]
+    fn commit(&mut self, tested: &Tested, kept: u64, state: &RasterState, front: bool) {
+        let passed = tested.passed & kept;
+        if state.depth_write {
+            self.depth.write(tested.depth, passed);
+        }
+        if let (Some(ref stencil), Some(mut plane)) = (state.stencil, self.stencil()) {
+            let face = stencil.face(front);
+            face.update(face.fail, &mut plane, tested.stencil_fail & kept);
+            face.update(face.depth_fail, &mut plane, tested.depth_fail & kept);
+            face.update(face.pass, &mut plane, passed);
+            self.set_stencil(plane);
+        }
+    }
+}
+
+/// The outcome of the stencil and depth tests of a tile
+#[derive(Clone, Copy)]
+struct Tested {
+    /// the window depth of each pixel
+    depth: f32x8x8,
+    /// the pixels that passed both tests
+    passed: u64,
+    stencil_fail: u64,
+    /// the pixels that passed the stencil test but failed the depth test
+    depth_fail: u64
+}
+
+/// The stencil of tile `i` of a plane, or nothing if there is no stencil
+#[inline]
+fn stencil_at(stencil: &mut [StencilTile], i: usize) -> &mut [StencilTile] {
+    if stencil.is_empty() {
+        stencil
+    } else {
+        &mut stencil[i..i+1]
+    }
+}
+
+/// Everything a tile needs to know about a primitive that has been
+/// clipped and projected, it is set up once and shared by every tile.
+#[derive(Clone, Copy, Debug)]
+pub struct Setup {
+    /// the depth of each vertex
+    pub z: Vector3<f32>,
+    pub inv_w: Vector3<f32>,
+    pub bary: Barycentric,
+    pub edges: Edges,
+    /// the weights of each vertex relative to the source triangle
+    pub weights: Triangle<[f32; 3]>,
+    pub front: bool,
+    /// the coverage of an anti-aliased line, used to scale the alpha
+    pub coverage: Option<LineCoverage>,
+    /// the line a one pixel wide aliased line picks its pixels with
+    pub diamond: Option<LineDiamond>,
+    /// the point sprite the triangle belongs to
+    pub point: Option<PointSprite>
+}
+
+/// The position of pixel `i` in the tile at `pos`
+#[inline]
+fn pixel(pos: Vector2<f32>, scale: Vector2<f32>, i: TileIndex) -> Vector2<f32> {
+    pos + vec2(i.x() as f32 * scale.x, i.y() as f32 * scale.y)
+}
+
+/// Perspective correct the screen space weights `w` of a pixel, returns
+/// the perspective correct and the screen space weights mapped back onto
+/// the source triangle.
+#[inline]
+fn source_weights(setup: &Setup, w: [f32; 3]) -> ([f32; 3], [f32; 3]) {
+    let inv_w = setup.inv_w;
+    let p = [w[0] * inv_w.x, w[1] * inv_w.y, w[2] * inv_w.z];
+    let sum = (p[0] + p[1] + p[2]).recip();
+    let p = [p[0] * sum, p[1] * sum, p[2] * sum];
+
+    let s = &setup.weights;
+    let weights = Triangle::new(&s.x, &s.y, &s.z);
+    (Interpolate::interpolate(weights, p),
+     Interpolate::interpolate(weights, w))
+}
+
+/// The derivatives of a 2x2 quad, the attributes are linear in the
+/// weights so they are interpolated from the difference of the weights.
+struct QuadDerivatives<'a, T: 'a> {
+    t: Triangle<&'a T>,
+    dx: ([f32; 3], [f32; 3]),
+    dy: ([f32; 3], [f32; 3])
+}
+
+impl<'a, T, O> Derivatives<O> for QuadDerivatives<'a, T> where T: Interpolate<Out=O> {
+    #[inline]
+    fn ddx(&self) -> O { Interpolate::interpolate_screen(self.t, self.dx.0, self.dx.1) }
+    #[inline]
+    fn ddy(&self) -> O { Interpolate::interpolate_screen(self.t, self.dy.0, self.dy.1) }
+}
+
+/// Interpolate the attributes at the source weights `w` and shade them,
+/// `pos` is the position of the pixel. Returns `None` if the fragment
+/// was discarded.
+#[inline]
+fn shade<F, T, O, P>(w: ([f32; 3], [f32; 3]),
+                     d: &QuadDerivatives<T>,
+                     pos: Vector2<f32>,
+                     scale: Vector2<f32>,
+                     setup: &Setup,
+                     t: Triangle<&T>,
+                     fragment: &F) -> Option<P> where
+          T: Interpolate<Out=O>,
+          F: ContextFragment<O, Color=P> {
+
+    let frag = Interpolate::interpolate_screen(t, w.0, w.1);
+    fragment.fragment_with(frag, &FragmentContext {
+        front: setup.front,
+        point: setup.point.map(|point| point.coord(pos, scale)),
+        derivatives: d
+    })
+}
+
+/// Scale the alpha of `color` by the coverage of the pixel at `pos`
+#[inline]
+fn cover<P>(setup: &Setup, fixed: Option<FixedFunction<P>>, pos: Vector2<f32>, scale: Vector2<f32>, color: P) -> P {
+    match (setup.coverage, fixed) {
+        (Some(ref c), Some(f)) => (f.scale_alpha)(color, c.coverage(pos, scale)),
+        _ => color
+    }
+}
+
+/// Blend `new` over `dst` with the fixed function blend state, or with
+/// the shader if there is none or the colour can not be blended.
+#[inline]
+fn blend<S, T, P: Copy>(state: &RasterState, fixed: Option<FixedFunction<P>>, shader: &S, dst: &mut P, new: P) where
+          S: TileShader<T, P> {
+
+    *dst = match (fixed, state.blend_targets, state.blend) {
+        (Some(f), Some(ref targets), _) => (f.blend)(*dst, new, targets),
+        (Some(f), None, Some(blend)) => (f.blend)(*dst, new, &[Some(blend); 4]),
+        _ => shader.blend(*dst, new)
+    };
+}
+
+/// How the covered pixels of a tile are turned into colours. A
+/// `ContextFragment` is wrapped in `PerPixel` and shades one pixel at a time,
+/// a `WideFragment` is wrapped in `Wide` and shades the whole tile at once.
+pub trait TileShader<T, P> {
+    /// Write the colour of every pixel in `mask` to `color`, returns
+    /// the pixels that were not discarded.
+    fn shade_tile(&self,
+                  pos: Vector2<f32>,
+                  scale: Vector2<f32>,
+                  setup: &Setup,
+                  mask: TileMask,
+                  t: Triangle<&T>,
+                  color: &mut [P; 64]) -> u64;
+
+    fn blend(&self, dst: P, new: P) -> P;
+}
+
+/// Shade a tile one pixel at a time with a `ContextFragment`
+pub struct PerPixel<F>(pub F);
+
+impl<F, T, O, P> TileShader<T, P> for PerPixel<F>
+    where F: ContextFragment<O, Color=P>,
+          T: Interpolate<Out=O> {
+
+    #[inline]
+    fn shade_tile(&self,
+                  pos: Vector2<f32>,
+                  scale: Vector2<f32>,
+                  setup: &Setup,
+                  mask: TileMask,
+                  t: Triangle<&T>,
+                  color: &mut [P; 64]) -> u64 {
+
+        #[inline]
+        fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] { [a[0] - b[0], a[1] - b[1], a[2] - b[2]] }
+
+        let (u, v) = (mask.u.to_array(), mask.v.to_array());
+        let mut kept = 0;
+        for q in 0..16 {
+            // the lower left pixel of the quad, followed by the pixel to its
+            // right, the pixel above it and the pixel diagonal to it
+            let i = (q & 3) * 2 + (q >> 2) * 16;
+            let quad = [i, i + 1, i + 8, i + 9];
+            let covered = mask.mask & (0x303 << i);
+            if covered == 0 {
+                continue;
+            }
+
+            // the helper pixels of the quad that are outside of the triangle
+            // only take part in the derivatives, they are never written
+            let weights = |i: usize| source_weights(setup, [1. - (u[i] + v[i]), u[i], v[i]]);
+            let w = [weights(quad[0]), weights(quad[1]), weights(quad[2]), weights(quad[3])];
+            let d = QuadDerivatives {
+                t: t,
+                dx: (sub(w[1].0, w[0].0), sub(w[1].1, w[0].1)),
+                dy: (sub(w[2].0, w[0].0), sub(w[2].1, w[0].1))
+            };
+
+            for (k, &i) in quad.iter().enumerate() {
+                if covered & (1 << i) == 0 {
+                    continue;
+                }
+                let p = pixel(pos, scale, TileIndex(i as u32));
+                if let Some(new) = shade(w[k], &d, p, scale, setup, t, &self.0) {
+                    color[i] = new;
+                    kept |= 1 << i;
+                }
+            }
+        }
+        kept
+    }
+
+    #[inline]
+    fn blend(&self, dst: P, new: P) -> P { self.0.blend(dst, new) }
+}
+
+/// Shade a whole tile at once with a `WideFragment`
+pub struct Wide<F>(pub F);
+
+impl<F, T, O, P> TileShader<T, P> for Wide<F>
+    where F: WideFragment<O, Color=P>,
+          T: InterpolateWide<Wide=O> {
+
+    #[inline]
+    fn shade_tile(&self,
+                  _: Vector2<f32>,
+                  _: Vector2<f32>,
+                  setup: &Setup,
+                  mask: TileMask,
+                  t: Triangle<&T>,
+                  color: &mut [P; 64]) -> u64 {
+
+        // perspective correct the screen space weights
+        let w = mask.weights();
+        let inv_w = setup.inv_w;
+        let p = [w[0] * inv_w.x, w[1] * inv_w.y, w[2] * inv_w.z];
+        let sum = f32x8x8::broadcast(1.) / (p[0] + p[1] + p[2]);
+        let p = [p[0] * sum, p[1] * sum, p[2] * sum];
+
+        // map the weights of the clipped triangle back onto the source triangle
+        let s = &setup.weights;
+        let src = |w: [f32x8x8; 3]| {
+            [w[0] * s.x[0] + w[1] * s.y[0] + w[2] * s.z[0],
+             w[0] * s.x[1] + w[1] * s.y[1] + w[2] * s.z[1],
+             w[0] * s.x[2] + w[1] * s.y[2] + w[2] * s.z[2]]
+        };
+
+        let attrs = InterpolateWide::interpolate_wide_screen(t, src(p), src(w));
+        self.0.fragment_wide(attrs, mask.mask, color) & mask.mask
     }
+
+    #[inline]
+    fn blend(&self, dst: P, new: P) -> P { self.0.blend(dst, new) }
 }
 
 #[derive(Copy)]
@@ -139,64 +741,290 @@ impl<T: Copy> Clone for Quad<T> {
     }
 }
 
-#[derive(Copy)]
+/// The sample positions for 2x, 4x and 8x multisampling in pixels,
+/// relative to the position that is sampled without multisampling.
+static SAMPLES_1X: [(f32, f32); 1] = [(0., 0.)];
+static SAMPLES_2X: [(f32, f32); 2] = [( 0.25,  0.25), (-0.25, -0.25)];
+static SAMPLES_4X: [(f32, f32); 4] = [(-0.125, -0.375), ( 0.375, -0.125),
+                                     (-0.375,  0.125), ( 0.125,  0.375)];
+static SAMPLES_8X: [(f32, f32); 8] = [( 0.0625, -0.1875), (-0.0625,  0.1875),
+                                     ( 0.3125,  0.0625), (-0.1875, -0.3125),
+                                     (-0.3125,  0.3125), (-0.4375, -0.0625),
+                                     ( 0.1875,  0.4375), ( 0.4375, -0.4375)];
+
+/// The offset of each of the 16 tiles in a group, in the order
+/// they are stored in `Quad<Quad<Tile<P>>>`
+const QUAD_OFFSET: [(f32, f32); 4] = [(0., 0.), (1., 0.), (0., 1.), (1., 1.)];
+
+/// Get the standard sample pattern for a sample count
+pub fn sample_pattern(samples: usize) -> &'static [(f32, f32)] {
+    match samples {
+        1 => &SAMPLES_1X,
+        2 => &SAMPLES_2X,
+        4 => &SAMPLES_4X,
+        8 => &SAMPLES_8X,
+        _ => panic!("unsupported sample count {}", samples)
+    }
+}
+
 pub struct TileGroup<P> {
-    tiles: Quad<Quad<Tile<P>>>
+    tiles: Quad<Quad<Tile<P>>>,
+    /// the extra sample planes of a multisampled group, the
+    /// first sample is always stored in `tiles`
+    samples: Vec<Quad<Quad<Tile<P>>>>,
+    /// the depth of every tile of every sample plane
+    depth: DepthPlanes,
+    /// the stencil of every tile of every sample plane, 16 tiles per
+    /// plane in the order they are rastered. Empty without a stencil,
+    /// or if the depth format packs it into the depth.
+    stencil: Vec<StencilTile>,
+    /// the colours of a tile are shaded into this before they are
+    /// blended, so the colours of the tile are never copied
+    scratch: [P; 64]
 }
 
 impl<P: Copy> Clone for TileGroup<P> {
     fn clone(&self) -> TileGroup<P> {
         TileGroup {
-            tiles: self.tiles
+            tiles: self.tiles,
+            samples: self.samples.clone(),
+            depth: self.depth.clone(),
+            stencil: self.stencil.clone(),
+            scratch: self.scratch
         }
     }
 }
 
 impl<P: Copy> TileGroup<P> {
     pub fn new(p: P) -> TileGroup<P> {
+        TileGroup::with_format(p, &FrameFormat::new())
+    }
+
+    pub fn with_format(p: P, format: &FrameFormat) -> TileGroup<P> {
+        let samples = format.samples;
+        sample_pattern(samples);
         TileGroup {
-            tiles: Quad::new(Quad::new(Tile::new(p)))
+            tiles: Quad::new(Quad::new(Tile::new(p))),
+            samples: (1..samples).map(|_| Quad::new(Quad::new(Tile::new(p)))).collect(),
+            depth: DepthPlanes::new(format, 16 * samples),
+            stencil: if format.stencil && format.depth != DepthFormat::Unorm24 {
+                (0..16 * samples).map(|_| StencilTile([0; 64])).collect()
+            } else {
+                Vec::new()
+            },
+            scratch: [p; 64]
         }
     }
 
+    /// The number of samples per pixel
+    #[inline]
+    pub fn samples(&self) -> usize { self.samples.len() + 1 }
+
+    /// Write the pixels out, for a multisampled group only the
+    /// first sample is written. See `resolve`.
     pub fn write<W: Put<P>>(&self, x: u32, y: u32, v: &mut W) {
         self.tiles.write(x, y, v);
     }
 
-    pub fn raster<F, T, O>(&mut self,
-                           pos: Vector2<f32>,
-                           scale: Vector2<f32>,
-                           z: &Vector3<f32>,
-                           bary: &Barycentric,
-                           t: &Triangle<T>,
-                           fragment: &F) where
-              T: Interpolate<Out=O>,
-              F: Fragment<O, Color=P> {
+    /// Write the window depth of each pixel out, for a multisampled
+    /// group this is the depth of the first sample.
+    pub fn write_depth<W: Put<f32>>(&self, x: u32, y: u32, v: &mut W) {
+        for i in 0..4 {
+            for j in 0..4 {
+                let depth = self.depth.read(i * 4 + j);
+                let ox = x + (QUAD_OFFSET[i].0 * 16. + QUAD_OFFSET[j].0 * 8.) as u32;
+                let oy = y + (QUAD_OFFSET[i].1 * 16. + QUAD_OFFSET[j].1 * 8.) as u32;
+                for k in (0..64).map(|k| TileIndex(k)) {
+                    v.put(ox + k.x(), oy + k.y(), depth[k.0 as usize]);
+                }
+            }
+        }
+    }
+
+    /// Average all the samples of each pixel into a single sampled group
+    pub fn resolve(&self) -> TileGroup<P> where P: Planes {
+        let mut out = TileGroup {
+            tiles: self.tiles,
+            samples: Vec::new(),
+            depth: self.depth.first_plane(),
+            stencil: Vec::new(),
+            scratch: self.scratch
+        };
+
+        if self.samples.is_empty() {
+            return out;
+        }
+
+        let n = self.samples();
+        for i in 0..4 {
+            for j in 0..4 {
+                let dst = &mut out.tiles.0[i].0[j];
+                for k in 0..64 {
+                    let mut samples = [dst.color[k]; 8];
+                    for (s, plane) in self.samples.iter().enumerate() {
+                        samples[s + 1] = plane.0[i].0[j].color[k];
+                    }
+                    dst.color[k] = Planes::average(&samples[..n]);
+                }
+            }
+        }
+        out
+    }
+
 
-        self.tiles.raster(pos, scale, z, bary, t, fragment);
+    /// Coverage, stencil and depth are evaluated for every sample, but
+    /// each pixel is only shaded once. The shaded colour is written to
+    /// every sample of that pixel that passed.
+    fn raster_multisample<F, T>(&mut self,
+                                pos: Vector2<f32>,
+                                scale: Vector2<f32>,
+                                state: &RasterState,
+                                setup: &Setup,
+                                fixed: Option<FixedFunction<P>>,
+                                t: Triangle<&T>,
+                                fragment: &F) where
+              F: TileShader<T, P> {
+
+        let pattern = sample_pattern(self.samples());
+        let tsize = scale.mul_s(8.);
+        let TileGroup { ref mut tiles, ref mut samples, ref mut depth, ref mut stencil, scratch: ref mut color } = *self;
+
+        for i in 0..4 {
+            for j in 0..4 {
+                let (ox, oy) = (QUAD_OFFSET[i].0 * 2. + QUAD_OFFSET[j].0,
+                                QUAD_OFFSET[i].1 * 2. + QUAD_OFFSET[j].1);
+                let pos = pos + vec2(ox * tsize.x, oy * tsize.y);
+
+                let mut tests = [None; 8];
+                let mut covered = 0;
+                for (s, &(sx, sy)) in pattern.iter().enumerate() {
+                    let spos = pos + vec2(sx * scale.x, sy * scale.y);
+                    let mut mask = TileMask::new(spos, scale, setup);
+                    if mask.mask == 0 {
+                        continue;
+                    }
+
+                    let (_, buffers) = plane_mut(tiles, samples, depth, stencil, s);
+                    tests[s] = Some(buffers.tile(i * 4 + j).test(&mut mask, state, setup));
+                    covered |= mask.mask;
+                }
+
+                if covered == 0 {
+                    continue;
+                }
+
+                // shade at the pixel position, even if it is not covered
+                let mut mask = TileMask::new(pos, scale, setup);
+                mask.mask = covered;
+
+                let kept = fragment.shade_tile(pos, scale, setup, mask, t, color);
+                let mut m = kept;
+                while m != 0 {
+                    let k = m.trailing_zeros();
+                    m &= !(1 << k);
+                    color[k as usize] = cover(setup, fixed, pixel(pos, scale, TileIndex(k)), scale, color[k as usize]);
+                }
+
+                for s in 0..pattern.len() {
+                    let tested = match tests[s] {
+                        Some(tested) => tested,
+                        None => continue
+                    };
+                    let (plane, buffers) = plane_mut(tiles, samples, depth, stencil, s);
+                    buffers.tile(i * 4 + j).commit(&tested, kept, state, setup.front);
+                    let tile = &mut plane.0[i].0[j];
+
+                    let mut m = tested.passed & kept;
+                    while m != 0 {
+                        let k = m.trailing_zeros() as usize;
+                        m &= !(1 << k);
+                        blend(state, fixed, fragment, &mut tile.color[k], color[k]);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn raster<F, T>(&mut self,
+                        pos: Vector2<f32>,
+                        scale: Vector2<f32>,
+                        state: &RasterState,
+                        setup: &Setup,
+                        fixed: Option<FixedFunction<P>>,
+                        t: Triangle<&T>,
+                        fragment: &F) where
+              F: TileShader<T, P> {
+
+        if self.samples.is_empty() {
+            let buffers = DepthStencil {
+                depth: self.depth.slice(0, 16),
+                stencil: &mut self.stencil
+            };
+            self.tiles.raster(pos, scale, state, setup, fixed, buffers, &mut self.scratch, t, fragment);
+        } else {
+            self.raster_multisample(pos, scale, state, setup, fixed, t, fragment);
+        }
     }
 
-    pub fn clear(&mut self, p: P) {
+    /// Clear the colour to `p`, the depth to the window depth `depth`
+    /// and the stencil to `stencil`
+    pub fn clear(&mut self, p: P, depth: f32, stencil: u8) {
         Raster::clear(&mut self.tiles, p);
+        for plane in self.samples.iter_mut() {
+            Raster::clear(plane, p);
+        }
+        self.depth.clear(depth, stencil);
+        for tile in self.stencil.iter_mut() {
+            tile.0 = [stencil; 64];
+        }
     }
 
     pub fn map<S, F>(&mut self, src: &TileGroup<S>, f: &F) where F: Mapping<S, Out=P>, S: Copy {
+        assert_eq!(self.samples.len(), src.samples.len());
         self.tiles.map(&src.tiles, f);
+        for (dst, src) in self.samples.iter_mut().zip(src.samples.iter()) {
+            dst.map(src, f);
+        }
+    }
+}
+
+/// The tiles, the depth and the stencil of sample plane `sample` of a group
+#[inline]
+fn plane_mut<'a, P>(tiles: &'a mut Quad<Quad<Tile<P>>>,
+                    samples: &'a mut [Quad<Quad<Tile<P>>>],
+                    depth: &'a mut DepthPlanes,
+                    stencil: &'a mut [StencilTile],
+                    sample: usize) -> (&'a mut Quad<Quad<Tile<P>>>, DepthStencil<'a>) {
+    let stencil = if stencil.is_empty() {
+        stencil
+    } else {
+        &mut stencil[sample * 16..(sample + 1) * 16]
+    };
+    let buffers = DepthStencil {
+        depth: depth.slice(sample * 16, (sample + 1) * 16),
+        stencil: stencil
+    };
+    if sample == 0 {
+        (tiles, buffers)
+    } else {
+        (&mut samples[sample - 1], buffers)
     }
 }
 
 pub trait Raster<P> {
     fn mask(&self) -> u32 { 0xFFFF_FFFF - (self.size() - 1) }
     fn size(&self) -> u32;
-    fn raster<F, T, O>(&mut self,
-                       pos: Vector2<f32>,
-                       scale: Vector2<f32>,
-                       z: &Vector3<f32>,
-                       bary: &Barycentric,
-                       t: &Triangle<T>,
-                       fragment: &F) where
-              T: Interpolate<Out=O>,
-              F: Fragment<O, Color=P>;
+    fn raster<F, T>(&mut self,
+                    pos: Vector2<f32>,
+                    scale: Vector2<f32>,
+                    state: &RasterState,
+                    setup: &Setup,
+                    fixed: Option<FixedFunction<P>>,
+                    buffers: DepthStencil,
+                    scratch: &mut [P; 64],
+                    t: Triangle<&T>,
+                    fragment: &F) where
+              F: TileShader<T, P>;
 
     fn clear(&mut self, p: P);
     fn write<W: Put<P>>(&self, x: u32, y: u32, v: &mut W);
@@ -211,21 +1039,24 @@ impl<I, P: Copy> Raster<P> for Quad<I> where I: Raster<P> {
     fn size(&self) -> u32 { 2 * self.0[0].size() }
 
     #[inline]
-    fn raster<F, T, O>(&mut self,
-                       pos: Vector2<f32>,
-                       scale: Vector2<f32>,
-                       z: &Vector3<f32>,
-                       bary: &Barycentric,
-                       t: &Triangle<T>,
-                       fragment: &F) where
-              T: Interpolate<Out=O>,
-              F: Fragment<O, Color=P> {
+    fn raster<F, T>(&mut self,
+                    pos: Vector2<f32>,
+                    scale: Vector2<f32>,
+                    state: &RasterState,
+                    setup: &Setup,
+                    fixed: Option<FixedFunction<P>>,
+                    buffers: DepthStencil,
+                    scratch: &mut [P; 64],
+                    t: Triangle<&T>,
+                    fragment: &F) where
+              F: TileShader<T, P> {
 
+        let (s0, s1, s2, s3) = buffers.quarters();
         let tsize = scale.mul_s(self.0[0].size() as f32);
-        self.0[0].raster(pos,                     scale, z, bary, t, fragment);
-        self.0[1].raster(pos + vec2(tsize.x, 0.), scale, z, bary, t, fragment);
-        self.0[2].raster(pos + vec2(0., tsize.y), scale, z, bary, t, fragment);
-        self.0[3].raster(pos + tsize,             scale, z, bary, t, fragment);
+        self.0[0].raster(pos,                     scale, state, setup, fixed, s0, scratch, t, fragment);
+        self.0[1].raster(pos + vec2(tsize.x, 0.), scale, state, setup, fixed, s1, scratch, t, fragment);
+        self.0[2].raster(pos + vec2(0., tsize.y), scale, state, setup, fixed, s2, scratch, t, fragment);
+        self.0[3].raster(pos + tsize,             scale, state, setup, fixed, s3, scratch, t, fragment);
     }
 
     #[inline]
@@ -258,28 +1089,38 @@ impl<P: Copy> Raster<P> for Tile<P> {
     fn size(&self) -> u32 { 8 }
 
     #[inline]
-    fn raster<F, T, O>(&mut self,
-                       pos: Vector2<f32>,
-                       scale: Vector2<f32>,
-                       z: &Vector3<f32>,
-                       bary: &Barycentric,
-                       t: &Triangle<T>,
-                       fragment: &F) where
-              T: Interpolate<Out=O>,
-              F: Fragment<O, Color=P> {
-
-        let mut mask = TileMask::new(pos, scale, &bary);
+    fn raster<F, T>(&mut self,
+                    pos: Vector2<f32>,
+                    scale: Vector2<f32>,
+                    state: &RasterState,
+                    setup: &Setup,
+                    fixed: Option<FixedFunction<P>>,
+                    buffers: DepthStencil,
+                    scratch: &mut [P; 64],
+                    t: Triangle<&T>,
+                    fragment: &F) where
+              F: TileShader<T, P> {
+
+        let mut mask = TileMask::new(pos, scale, setup);
         if mask.mask == 0 {
             return;
         }
 
-        mask.mask_with_depth(z, &mut self.depth);
-        for (i, w) in mask.iter() {
-            let frag = Interpolate::interpolate(t, w);
-            let new = fragment.fragment(frag);
-            let dst = unsafe { self.color.get_unchecked_mut(i.0 as usize) };
-            *dst = fragment.blend(*dst, new);
+        // shade before writing the depth or the stencil, discarded
+        // pixels must not write either of them
+        let mut buffers = buffers;
+        let tested = buffers.test(&mut mask, state, setup);
+        let kept = fragment.shade_tile(pos, scale, setup, mask, t, scratch);
+
+        let mut m = tested.passed & kept;
+        while m != 0 {
+            let i = m.trailing_zeros();
+            m &= !(1 << i);
+            let new = cover(setup, fixed, pixel(pos, scale, TileIndex(i)), scale, scratch[i as usize]);
+            let dst = unsafe { self.color.get_unchecked_mut(i as usize) };
+            blend(state, fixed, fragment, dst, new);
         }
+        buffers.commit(&tested, kept, state, setup.front);
     }
 
     #[inline]
@@ -291,7 +1132,6 @@ impl<P: Copy> Raster<P> for Tile<P> {
 
     #[inline]
     fn clear(&mut self, p: P) {
-        self.depth = f32x8x8::broadcast(1.);
         self.color = [p; 64];
     }
 }
@@ -310,7 +1150,10 @@ pub trait Put<P> {
 
 impl Put<Rgba<u8>> for ImageBuffer<Rgba<u8>, Vec<u8>> {
     fn put(&mut self, x: u32, y: u32, p: Rgba<u8>) {
-        let h = self.height();
-        self.put_pixel(x, h - 1 - y, p);
+        let (w, h) = (self.width(), self.height());
+        // partial tiles on the edge of the frame extend past the image
+        if x < w && y < h {
+            self.put_pixel(x, h - 1 - y, p);
+        }
     }
 }
@@ -0,0 +1,50 @@
+extern crate rusterize;
+extern crate image;
+
+use image::Rgba;
+use rusterize::{Blend, BlendFactor, Equation};
+
+#[test]
+fn alpha() {
+    let src = [1., 0., 0., 0.5];
+    let dst = [0., 0., 1., 1.];
+    assert_eq!(Blend::alpha().apply(src, dst), [0.5, 0., 0.5, 1.]);
+}
+
+#[test]
+fn premultiplied() {
+    let src = [0.5, 0., 0., 0.5];
+    let dst = [0., 0., 1., 1.];
+    assert_eq!(Blend::premultiplied().apply(src, dst), [0.5, 0., 0.5, 1.]);
+}
+
+#[test]
+fn additive_saturates() {
+    let src = Rgba([200u8, 100, 0, 255]);
+    let dst = Rgba([100u8, 100, 0, 255]);
+    assert_eq!(Blend::additive().apply(src, dst), Rgba([255, 200, 0, 255]));
+}
+
+#[test]
+fn subtract() {
+    let src = [0.75, 0.5, 0.25, 1.];
+    let dst = [0.25, 0.25, 0.25, 1.];
+
+    let blend = Blend::new(Equation::Subtract, BlendFactor::One, BlendFactor::One);
+    assert_eq!(blend.apply(src, dst), [0.5, 0.25, 0., 0.]);
+
+    let blend = Blend::new(Equation::ReverseSubtract, BlendFactor::One, BlendFactor::One);
+    assert_eq!(blend.apply(src, dst), [-0.5, -0.25, 0., 0.]);
+}
+
+#[test]
+fn min_max() {
+    let src = [0.75, 0.5, 0.25, 1.];
+    let dst = [0.25, 0.5, 0.75, 0.];
+
+    let blend = Blend::new(Equation::Min, BlendFactor::Zero, BlendFactor::Zero);
+    assert_eq!(blend.apply(src, dst), [0.25, 0.5, 0.25, 0.]);
+
+    let blend = Blend::new(Equation::Max, BlendFactor::Zero, BlendFactor::Zero);
+    assert_eq!(blend.apply(src, dst), [0.75, 0.5, 0.75, 1.]);
+}
@@ -0,0 +1,94 @@
+extern crate rusterize;
+extern crate genmesh;
+extern crate cgmath;
+
+use genmesh::Triangle;
+use cgmath::Vector4;
+use rusterize::clip::clip_triangle;
+
+#[test]
+fn inside() {
+    let t = Triangle::new(Vector4::new(-0.5, -0.5, 0., 1.),
+                          Vector4::new( 0.5, -0.5, 0., 1.),
+                          Vector4::new( 0.0,  0.5, 0., 1.));
+
+    let out: Vec<_> = clip_triangle(t).collect();
+    assert_eq!(out.len(), 1);
+    assert_eq!(out[0].x.weight, [1., 0., 0.]);
+    assert_eq!(out[0].y.weight, [0., 1., 0.]);
+    assert_eq!(out[0].z.weight, [0., 0., 1.]);
+}
+
+#[test]
+fn outside_near() {
+    let t = Triangle::new(Vector4::new(-0.5, -0.5, -2., 1.),
+                          Vector4::new( 0.5, -0.5, -2., 1.),
+                          Vector4::new( 0.0,  0.5, -2., 1.));
+
+    assert_eq!(clip_triangle(t).count(), 0);
+}
+
+#[test]
+fn outside_far() {
+    let t = Triangle::new(Vector4::new(-0.5, -0.5, 2., 1.),
+                          Vector4::new( 0.5, -0.5, 2., 1.),
+                          Vector4::new( 0.0,  0.5, 2., 1.));
+
+    assert_eq!(clip_triangle(t).count(), 0);
+}
+
+#[test]
+fn behind_camera() {
+    // one vertex is behind the eye (w < 0), clipping must
+    // leave only vertices with a positive w
+    let t = Triangle::new(Vector4::new(-0.5, -0.5,  0.5, 1.),
+                          Vector4::new( 0.5, -0.5,  0.5, 1.),
+                          Vector4::new( 0.0,  0.5, -2.0, -1.));
+
+    let out: Vec<_> = clip_triangle(t).collect();
+    assert_eq!(out.len(), 2);
+    for t in out.iter() {
+        for v in [t.x, t.y, t.z].iter() {
+            assert!(v.pos.w > 0.);
+            assert!(v.pos.z >= -v.pos.w - 1e-5);
+            let sum = v.weight[0] + v.weight[1] + v.weight[2];
+            assert!((sum - 1.).abs() < 1e-5);
+        }
+    }
+}
+
+#[test]
+fn near_and_far() {
+    // spans both planes, the polygon is cut twice
+    let t = Triangle::new(Vector4::new(-0.5, -0.5, -3., 1.),
+                          Vector4::new( 0.5, -0.5,  3., 1.),
+                          Vector4::new( 0.0,  0.5,  0., 1.));
+
+    let out: Vec<_> = clip_triangle(t).collect();
+    assert_eq!(out.len(), 3);
+    for t in out.iter() {
+        for v in [t.x, t.y, t.z].iter() {
+            assert!(v.pos.z >= -1. - 1e-5);
+            assert!(v.pos.z <= 1. + 1e-5);
+        }
+    }
+}
+
+#[test]
+fn guard_band() {
+    use rusterize::clip::GUARD_BAND;
+
+    // reaches far past the frame, it is cut down to the guard band
+    let t = Triangle::new(Vector4::new(-1e6, -1e6, 0., 1.),
+                          Vector4::new( 3e6, -1e6, 0., 1.),
+                          Vector4::new(-1e6,  3e6, 0., 1.));
+
+    let out: Vec<_> = clip_triangle(t).collect();
+    assert!(out.len() > 0);
+    for t in out.iter() {
+        for v in [t.x, t.y, t.z].iter() {
+            assert!(v.pos.x.abs() <= GUARD_BAND * v.pos.w * 1.0001);
+            assert!(v.pos.y.abs() <= GUARD_BAND * v.pos.w * 1.0001);
+        }
+    }
+}
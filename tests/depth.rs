@@ -0,0 +1,179 @@
+extern crate rusterize;
+extern crate cgmath;
+extern crate genmesh;
+extern crate image;
+
+use cgmath::*;
+use genmesh::{generators, Triangulate, MapToVertices};
+use image::Rgba;
+use rusterize::{Frame, FrameFormat, Fragment, RasterState, DepthFormat, DepthRange, DepthOutput, reversed_z};
+
+#[derive(Clone)]
+struct White;
+
+impl Fragment<[f32; 4]> for White {
+    type Color = Rgba<u8>;
+
+    fn fragment(&self, _: [f32; 4]) -> Rgba<u8> { Rgba([255, 255, 255, 255]) }
+}
+
+#[test]
+fn quantize() {
+    let range = DepthRange::NegativeOneToOne;
+    assert_eq!(DepthFormat::Float32.quantize(0.123456, range), 0.123456);
+
+    // the ends of the range are exact
+    for &format in [DepthFormat::Unorm16, DepthFormat::Unorm24].iter() {
+        assert_eq!(format.quantize(-1., range), -1.);
+        assert_eq!(format.quantize(1., range), 1.);
+        assert_eq!(format.quantize(-2., range), -1.);
+    }
+
+    // two depths closer than a step of 16 bits are merged, 24 bits keeps them apart
+    let (a, b) = (0.5, 0.5 + 1. / 200000.);
+    assert_eq!(DepthFormat::Unorm16.quantize(a, range), DepthFormat::Unorm16.quantize(b, range));
+    assert!(DepthFormat::Unorm24.quantize(a, range) != DepthFormat::Unorm24.quantize(b, range));
+
+    // in zero to one the step is half as large
+    let step = DepthFormat::Unorm16.quantize(0.6 / 65535., DepthRange::ZeroToOne);
+    assert_eq!(step, 1. / 65535.);
+}
+
+#[test]
+fn encode() {
+    // the ends of the window depth are exact and it is clamped
+    for &format in [DepthFormat::Unorm16, DepthFormat::Unorm24].iter() {
+        assert_eq!(format.decode(format.encode(0.)), 0.);
+        assert_eq!(format.decode(format.encode(1.)), 1.);
+        assert_eq!(format.encode(2.), format.encode(1.));
+    }
+    assert_eq!(DepthFormat::Unorm16.encode(1.), 65535);
+    assert_eq!(DepthFormat::Unorm24.encode(1.), 16777215);
+}
+
+#[test]
+fn stored_clear() {
+    // the cleared depth is a little behind the plane at a window depth
+    // of 0.75, but they are stored as the same 16 bit depth so the plane
+    // does not pass `Less`
+    let format = FrameFormat { depth: DepthFormat::Unorm16, .. FrameFormat::new() };
+    let mut frame = Frame::with_format(16, 16, format, Rgba([0u8, 0, 0, 0]));
+    frame.clear_with(Rgba([0, 0, 0, 0]), 0.75 + 1. / 400000., 0);
+    let plane = generators::Plane::new()
+        .triangulate()
+        .vertex(|v| [v.0, v.1, 0.5, 1.]);
+    frame.raster(plane, White);
+    assert!(frame.to_image().pixels().all(|p| p.0[0] == 0));
+
+    // with floating point depth the plane is in front
+    let mut frame = Frame::new(16, 16, Rgba([0u8, 0, 0, 0]));
+    frame.clear_with(Rgba([0, 0, 0, 0]), 0.75 + 1. / 400000., 0);
+    let plane = generators::Plane::new()
+        .triangulate()
+        .vertex(|v| [v.0, v.1, 0.5, 1.]);
+    frame.raster(plane, White);
+    assert!(frame.to_image().pixels().all(|p| p.0[0] == 255));
+}
+
+#[test]
+fn stored_readback() {
+    // the depth is read back as it was stored
+    for &depth in [DepthFormat::Unorm16, DepthFormat::Unorm24, DepthFormat::Float32].iter() {
+        let format = FrameFormat { depth: depth, .. FrameFormat::new() };
+        let mut frame = Frame::with_format(16, 16, format, Rgba([0u8, 0, 0, 0]));
+        let plane = generators::Plane::new()
+            .triangulate()
+            .vertex(|v| [v.0, v.1, 0.3, 1.]);
+        frame.raster(plane, White);
+
+        let stored = if depth == DepthFormat::Float32 { 0.65 } else { depth.decode(depth.encode(0.65)) };
+        let window = frame.depth_to_vec(DepthOutput::Window(DepthRange::NegativeOneToOne));
+        assert!(window.iter().all(|&w| (w - stored).abs() < 1e-6));
+    }
+}
+
+#[test]
+fn reversed_clear() {
+    // a reversed-Z frame starts out and is cleared at depth 0, so
+    // anything in front of the far plane passes `Greater`
+    let format = FrameFormat { reversed_z: true, .. FrameFormat::new() };
+    let mut frame = Frame::with_format(16, 16, format, Rgba([0u8, 0, 0, 0]));
+    for _ in 0..2 {
+        let plane = generators::Plane::new()
+            .triangulate()
+            .vertex(|v| [v.0, v.1, 0.1, 1.]);
+        frame.raster_with(&RasterState::reversed_z(), plane, White);
+        assert!(frame.to_image().pixels().all(|p| p.0[0] == 255));
+
+        frame.clear(Rgba([0, 0, 0, 0]));
+        let depth = frame.depth_to_vec(DepthOutput::Window(DepthRange::ZeroToOne));
+        assert!(depth.iter().all(|&z| z == 0.));
+    }
+}
+
+#[test]
+fn reversed() {
+    let proj = reversed_z(perspective(deg(90.), 1., 1., 10.));
+    let depth = |z: f32| {
+        let v = proj.mul_v(&Vector4::new(0., 0., z, 1.));
+        v.z / v.w
+    };
+
+    // the near plane is at 1 and the far plane is at 0
+    assert!((depth(-1.) - 1.).abs() < 1e-6);
+    assert!(depth(-10.).abs() < 1e-6);
+    assert!(depth(-2.) > depth(-3.));
+}
+
+#[test]
+fn linearize() {
+    let (near, far) = (1., 10.);
+    let ndc = |proj: Matrix4<f32>, z: f32| {
+        let v = proj.mul_v(&Vector4::new(0., 0., z, 1.));
+        v.z / v.w
+    };
+
+    let proj = perspective(deg(90.), 1., near, far);
+    let linear = DepthOutput::Linear { near: near, far: far };
+    for &z in [1., 2.5, 7., 10.].iter() {
+        assert!((linear.convert(ndc(proj, -z)) - z).abs() < 1e-4);
+    }
+
+    // the same projection with its depth moved into zero to one
+    let zero = DepthOutput::LinearZeroToOne { near: near, far: far };
+    for &z in [1., 2.5, 7., 10.].iter() {
+        assert!((zero.convert(ndc(proj, -z) * 0.5 + 0.5) - z).abs() < 1e-4);
+    }
+
+    let reversed = DepthOutput::LinearReversed { near: near, far: far };
+    for &z in [1., 2.5, 7., 10.].iter() {
+        assert!((reversed.convert(ndc(reversed_z(proj), -z)) - z).abs() < 1e-4);
+    }
+
+    assert_eq!(linear.normalize(-1.), 0.);
+    assert_eq!(linear.normalize(1.), 1.);
+    assert_eq!(DepthOutput::Window(DepthRange::NegativeOneToOne).convert(0.), 0.5);
+    assert_eq!(DepthOutput::Window(DepthRange::ZeroToOne).convert(0.25), 0.25);
+}
+
+#[test]
+fn readback() {
+    // a plane over the top half of the frame at a depth of 0.5
+    let mut frame = Frame::new(40, 20, Rgba([0u8, 0, 0, 0]));
+    let plane = generators::Plane::new()
+        .triangulate()
+        .vertex(|v| [v.0, v.1 * 0.5 + 0.5, 0.5, 1.]);
+    frame.raster(plane, White);
+
+    let depth = frame.depth_to_vec(DepthOutput::Window(DepthRange::NegativeOneToOne));
+    assert_eq!(depth.len(), 40 * 20);
+
+    // the first row is the top of the frame like to_image
+    assert!(depth[..40 * 10].iter().all(|&z| z == 0.75));
+    assert!(depth[40 * 10..].iter().all(|&z| z == 1.));
+
+    let img = frame.depth_to_image(DepthOutput::Window(DepthRange::NegativeOneToOne));
+    assert_eq!((img.width(), img.height()), (40, 20));
+    assert_eq!(img.get_pixel(5, 2).0[0], 49151);
+    assert_eq!(img.get_pixel(5, 17).0[0], 65535);
+}
@@ -5,6 +5,11 @@ extern crate genmesh;
 use genmesh::Triangle;
 use rusterize::Interpolate;
 
+/// The vertices of `t` as references, like the rasterizer passes them
+fn borrow<T>(t: &Triangle<T>) -> Triangle<&T> {
+    Triangle::new(&t.x, &t.y, &t.z)
+}
+
 #[test]
 fn test_f32() {
     let v001 = Triangle::new(0., 0., 1.);
@@ -15,17 +20,17 @@ fn test_f32() {
     let s010 = [0., 1., 0.];
     let s100 = [1., 0., 0.];
 
-    assert_eq!(Interpolate::interpolate(&v001, s001), 1.);
-    assert_eq!(Interpolate::interpolate(&v001, s010), 0.);
-    assert_eq!(Interpolate::interpolate(&v001, s100), 0.);
+    assert_eq!(Interpolate::interpolate(borrow(&v001), s001), 1.);
+    assert_eq!(Interpolate::interpolate(borrow(&v001), s010), 0.);
+    assert_eq!(Interpolate::interpolate(borrow(&v001), s100), 0.);
 
-    assert_eq!(Interpolate::interpolate(&v010, s001), 0.);
-    assert_eq!(Interpolate::interpolate(&v010, s010), 1.);
-    assert_eq!(Interpolate::interpolate(&v010, s100), 0.);
+    assert_eq!(Interpolate::interpolate(borrow(&v010), s001), 0.);
+    assert_eq!(Interpolate::interpolate(borrow(&v010), s010), 1.);
+    assert_eq!(Interpolate::interpolate(borrow(&v010), s100), 0.);
 
-    assert_eq!(Interpolate::interpolate(&v100, s001), 0.);
-    assert_eq!(Interpolate::interpolate(&v100, s010), 0.);
-    assert_eq!(Interpolate::interpolate(&v100, s100), 1.);
+    assert_eq!(Interpolate::interpolate(borrow(&v100), s001), 0.);
+    assert_eq!(Interpolate::interpolate(borrow(&v100), s010), 0.);
+    assert_eq!(Interpolate::interpolate(borrow(&v100), s100), 1.);
 }
 
 #[test]
@@ -38,17 +43,17 @@ fn test_f32_2() {
     let s010 = [0., 1., 0.];
     let s100 = [1., 0., 0.];
 
-    assert_eq!(Interpolate::interpolate(&v001, s001), [1., 2.]);
-    assert_eq!(Interpolate::interpolate(&v001, s010), [0., 0.]);
-    assert_eq!(Interpolate::interpolate(&v001, s100), [0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v001), s001), [1., 2.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v001), s010), [0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v001), s100), [0., 0.]);
 
-    assert_eq!(Interpolate::interpolate(&v010, s001), [0., 0.]);
-    assert_eq!(Interpolate::interpolate(&v010, s010), [1., 2.]);
-    assert_eq!(Interpolate::interpolate(&v010, s100), [0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v010), s001), [0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v010), s010), [1., 2.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v010), s100), [0., 0.]);
 
-    assert_eq!(Interpolate::interpolate(&v100, s001), [0., 0.]);
-    assert_eq!(Interpolate::interpolate(&v100, s010), [0., 0.]);
-    assert_eq!(Interpolate::interpolate(&v100, s100), [1., 2.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v100), s001), [0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v100), s010), [0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v100), s100), [1., 2.]);
 }
 
 
@@ -62,17 +67,17 @@ fn test_f32_3() {
     let s010 = [0., 1., 0.];
     let s100 = [1., 0., 0.];
 
-    assert_eq!(Interpolate::interpolate(&v001, s001), [1., 2., 3.]);
-    assert_eq!(Interpolate::interpolate(&v001, s010), [0., 0., 0.]);
-    assert_eq!(Interpolate::interpolate(&v001, s100), [0., 0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v001), s001), [1., 2., 3.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v001), s010), [0., 0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v001), s100), [0., 0., 0.]);
 
-    assert_eq!(Interpolate::interpolate(&v010, s001), [0., 0., 0.]);
-    assert_eq!(Interpolate::interpolate(&v010, s010), [1., 2., 3.]);
-    assert_eq!(Interpolate::interpolate(&v010, s100), [0., 0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v010), s001), [0., 0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v010), s010), [1., 2., 3.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v010), s100), [0., 0., 0.]);
 
-    assert_eq!(Interpolate::interpolate(&v100, s001), [0., 0., 0.]);
-    assert_eq!(Interpolate::interpolate(&v100, s010), [0., 0., 0.]);
-    assert_eq!(Interpolate::interpolate(&v100, s100), [1., 2., 3.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v100), s001), [0., 0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v100), s010), [0., 0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v100), s100), [1., 2., 3.]);
 }
 
 #[test]
@@ -85,15 +90,32 @@ fn test_f32_4() {
     let s010 = [0., 1., 0.];
     let s100 = [1., 0., 0.];
 
-    assert_eq!(Interpolate::interpolate(&v001, s001), [1., 2., 3., 4.]);
-    assert_eq!(Interpolate::interpolate(&v001, s010), [0., 0., 0., 0.]);
-    assert_eq!(Interpolate::interpolate(&v001, s100), [0., 0., 0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v001), s001), [1., 2., 3., 4.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v001), s010), [0., 0., 0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v001), s100), [0., 0., 0., 0.]);
+
+    assert_eq!(Interpolate::interpolate(borrow(&v010), s001), [0., 0., 0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v010), s010), [1., 2., 3., 4.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v010), s100), [0., 0., 0., 0.]);
+
+    assert_eq!(Interpolate::interpolate(borrow(&v100), s001), [0., 0., 0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v100), s010), [0., 0., 0., 0.]);
+    assert_eq!(Interpolate::interpolate(borrow(&v100), s100), [1., 2., 3., 4.]);
+}
+
+#[test]
+fn test_no_perspective() {
+    use rusterize::NoPerspective;
+
+    let v = Triangle::new(NoPerspective(0.), NoPerspective(1.), NoPerspective(2.));
+    let p = Triangle::new(0., 1., 2.);
 
-    assert_eq!(Interpolate::interpolate(&v010, s001), [0., 0., 0., 0.]);
-    assert_eq!(Interpolate::interpolate(&v010, s010), [1., 2., 3., 4.]);
-    assert_eq!(Interpolate::interpolate(&v010, s100), [0., 0., 0., 0.]);
+    let w = [0., 1., 0.];
+    let s = [0., 0., 1.];
 
-    assert_eq!(Interpolate::interpolate(&v100, s001), [0., 0., 0., 0.]);
-    assert_eq!(Interpolate::interpolate(&v100, s010), [0., 0., 0., 0.]);
-    assert_eq!(Interpolate::interpolate(&v100, s100), [1., 2., 3., 4.]);
-}
\ No newline at end of file
+    assert_eq!(Interpolate::interpolate_screen(borrow(&v), w, s), 2.);
+    assert_eq!(Interpolate::interpolate_screen(borrow(&p), w, s), 1.);
+
+    let t = Triangle::new((v.x, p.x), (v.y, p.y), (v.z, p.z));
+    assert_eq!(Interpolate::interpolate_screen(borrow(&t), w, s), (2., 1.));
+}
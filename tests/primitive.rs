@@ -0,0 +1,120 @@
+extern crate rusterize;
+extern crate genmesh;
+
+use genmesh::Triangle;
+use rusterize::{Topology, Assembly, ProvokingVertex};
+use rusterize::primitive::assemble;
+
+#[test]
+fn list() {
+    let idx = [0, 1, 2, 3, 4, 5, 6];
+    let t: Vec<_> = assemble(&idx, Topology::TriangleList).collect();
+    assert_eq!(t, vec![Triangle::new(0, 1, 2), Triangle::new(3, 4, 5)]);
+}
+
+#[test]
+fn strip() {
+    let idx = [0, 1, 2, 3, 4];
+    let t: Vec<_> = assemble(&idx, Topology::TriangleStrip).collect();
+    assert_eq!(t, vec![Triangle::new(0, 1, 2),
+                       Triangle::new(1, 3, 2),
+                       Triangle::new(2, 3, 4)]);
+}
+
+#[test]
+fn fan() {
+    let idx = [0, 1, 2, 3, 4];
+    let t: Vec<_> = assemble(&idx, Topology::TriangleFan).collect();
+    assert_eq!(t, vec![Triangle::new(1, 2, 0),
+                       Triangle::new(2, 3, 0),
+                       Triangle::new(3, 4, 0)]);
+}
+
+#[test]
+fn too_short() {
+    let idx = [0, 1];
+    assert_eq!(assemble(&idx, Topology::TriangleList).count(), 0);
+    assert_eq!(assemble(&idx, Topology::TriangleStrip).count(), 0);
+    assert_eq!(assemble(&idx, Topology::TriangleFan).count(), 0);
+}
+
+#[test]
+fn provoking_last() {
+    let idx = [0, 1, 2, 3, 4];
+    let mut a = Assembly::new(Topology::TriangleStrip);
+    a.provoking = ProvokingVertex::Last;
+    let t: Vec<_> = a.indices(&idx).collect();
+    assert_eq!(t, vec![Triangle::new(2, 0, 1),
+                       Triangle::new(3, 2, 1),
+                       Triangle::new(4, 2, 3)]);
+
+    a.topology = Topology::TriangleFan;
+    let t: Vec<_> = a.indices(&idx).collect();
+    assert_eq!(t, vec![Triangle::new(2, 0, 1),
+                       Triangle::new(3, 0, 2),
+                       Triangle::new(4, 0, 3)]);
+}
+
+#[test]
+fn restart() {
+    let mut a = Assembly::new(Topology::TriangleStrip);
+    a.restart = Some(!0);
+
+    let idx = [0, 1, 2, 3, !0, 4, 5, 6, !0, 7];
+    let t: Vec<_> = a.indices(&idx).collect();
+    assert_eq!(t, vec![Triangle::new(0, 1, 2),
+                       Triangle::new(1, 3, 2),
+                       Triangle::new(4, 5, 6)]);
+
+    a.topology = Topology::TriangleFan;
+    let t: Vec<_> = a.indices(&idx).collect();
+    assert_eq!(t, vec![Triangle::new(1, 2, 0),
+                       Triangle::new(2, 3, 0),
+                       Triangle::new(5, 6, 4)]);
+
+    a.topology = Topology::TriangleList;
+    let idx = [0, 1, !0, 2, 3, 4, 5, !0];
+    let t: Vec<_> = a.indices(&idx).collect();
+    assert_eq!(t, vec![Triangle::new(2, 3, 4)]);
+}
+
+#[test]
+fn vertices_match_indices() {
+    let idx = [0, 1, 2, 3, 4, 5];
+    for &topology in [Topology::TriangleList,
+                      Topology::TriangleStrip,
+                      Topology::TriangleFan].iter() {
+        for &provoking in [ProvokingVertex::First, ProvokingVertex::Last].iter() {
+            let mut a = Assembly::new(topology);
+            a.provoking = provoking;
+            let i: Vec<_> = a.indices(&idx).collect();
+            let v: Vec<_> = a.vertices(idx.iter().cloned()).collect();
+            assert_eq!(i, v);
+        }
+    }
+}
+
+#[test]
+fn diagonals() {
+    // the diagonal joins the first and the last index of each triangle
+    let idx = [0, 1, 2, 3, 4];
+    for &topology in [Topology::TriangleList,
+                      Topology::TriangleStrip,
+                      Topology::TriangleFan].iter() {
+        for &provoking in [ProvokingVertex::First, ProvokingVertex::Last].iter() {
+            let mut a = Assembly::new(topology);
+            a.provoking = provoking;
+            for (n, (t, d)) in a.indices(&idx).diagonals().enumerate() {
+                let v = [t.x, t.y, t.z];
+                let mut edge = [v[d], v[(d + 1) % 3]];
+                edge.sort();
+                let expected = match topology {
+                    Topology::TriangleList => [3 * n as u32, 3 * n as u32 + 2],
+                    Topology::TriangleStrip => [n as u32, n as u32 + 2],
+                    Topology::TriangleFan => [0, n as u32 + 2]
+                };
+                assert_eq!(edge, expected);
+            }
+        }
+    }
+}
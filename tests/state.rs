@@ -0,0 +1,64 @@
+extern crate rusterize;
+extern crate genmesh;
+extern crate cgmath;
+
+use genmesh::Triangle;
+use cgmath::Vector3;
+use rusterize::{RasterState, CullFace, FrontFace, DepthFunc};
+
+fn ccw() -> Triangle<Vector3<f32>> {
+    Triangle::new(Vector3::new(0., 0., 0.),
+                  Vector3::new(1., 0., 0.),
+                  Vector3::new(0., 1., 0.))
+}
+
+fn cw() -> Triangle<Vector3<f32>> {
+    Triangle::new(Vector3::new(0., 0., 0.),
+                  Vector3::new(0., 1., 0.),
+                  Vector3::new(1., 0., 0.))
+}
+
+#[test]
+fn cull_back() {
+    let state = RasterState::new();
+    assert_eq!(state.facing(&ccw()), Some(true));
+    assert_eq!(state.facing(&cw()), None);
+}
+
+#[test]
+fn cull_front() {
+    let state = RasterState{cull: CullFace::Front, .. RasterState::new()};
+    assert_eq!(state.facing(&ccw()), None);
+    assert_eq!(state.facing(&cw()), Some(false));
+}
+
+#[test]
+fn cull_none() {
+    let state = RasterState{cull: CullFace::None, front_face: FrontFace::Cw, .. RasterState::new()};
+    assert_eq!(state.facing(&ccw()), Some(false));
+    assert_eq!(state.facing(&cw()), Some(true));
+}
+
+#[test]
+fn degenerate() {
+    let state = RasterState{cull: CullFace::None, .. RasterState::new()};
+    let t = Triangle::new(Vector3::new(0., 0., 0.),
+                          Vector3::new(1., 1., 0.),
+                          Vector3::new(2., 2., 0.));
+    assert_eq!(state.facing(&t), None);
+}
+
+#[test]
+fn depth_func() {
+    let (less, greater) = (0b0001, 0b0100);
+    let equal = !(less | greater);
+
+    assert_eq!(DepthFunc::Never.mask(less, greater), 0);
+    assert_eq!(DepthFunc::Less.mask(less, greater), less);
+    assert_eq!(DepthFunc::LessEqual.mask(less, greater), less | equal);
+    assert_eq!(DepthFunc::Equal.mask(less, greater), equal);
+    assert_eq!(DepthFunc::Greater.mask(less, greater), greater);
+    assert_eq!(DepthFunc::GreaterEqual.mask(less, greater), greater | equal);
+    assert_eq!(DepthFunc::NotEqual.mask(less, greater), less | greater);
+    assert_eq!(DepthFunc::Always.mask(less, greater), !0);
+}
@@ -0,0 +1,185 @@
+extern crate rusterize;
+extern crate genmesh;
+extern crate image;
+
+use rusterize::{Frame, FrameFormat, Fragment, ContextFragment, FragmentContext, RasterState, Stencil, StencilFace, StencilOp, DepthFunc};
+use rusterize::{DepthFormat, DepthOutput, DepthRange};
+use genmesh::Triangle;
+use image::Rgba;
+
+#[test]
+fn test_equal() {
+    let mut plane = [0u8; 64];
+    plane[1] = 1;
+    plane[3] = 1;
+
+    let face = StencilFace{func: DepthFunc::Equal, reference: 1, .. StencilFace::new()};
+    assert_eq!(face.test(&plane, 0b1111), 0b1010);
+    assert_eq!(face.test(&plane, 0b0011), 0b0010);
+}
+
+#[test]
+fn read_mask() {
+    let mut plane = [0u8; 64];
+    plane[0] = 0xF1;
+
+    let face = StencilFace{func: DepthFunc::Equal, reference: 1, read_mask: 0x0F, .. StencilFace::new()};
+    assert_eq!(face.test(&plane, 0b1), 0b1);
+}
+
+#[test]
+fn operations() {
+    let mut plane = [0u8; 64];
+    let face = StencilFace{reference: 7, .. StencilFace::new()};
+
+    face.update(StencilOp::Replace, &mut plane, 0b11);
+    assert_eq!(&plane[..3], &[7, 7, 0]);
+
+    face.update(StencilOp::IncrementClamp, &mut plane, 0b110);
+    assert_eq!(&plane[..3], &[7, 8, 1]);
+
+    face.update(StencilOp::DecrementWrap, &mut plane, 0b100);
+    face.update(StencilOp::DecrementWrap, &mut plane, 0b100);
+    assert_eq!(plane[2], 255);
+
+    face.update(StencilOp::IncrementWrap, &mut plane, 0b100);
+    assert_eq!(plane[2], 0);
+
+    face.update(StencilOp::DecrementClamp, &mut plane, 0b100);
+    assert_eq!(plane[2], 0);
+
+    face.update(StencilOp::Invert, &mut plane, 0b1);
+    assert_eq!(plane[0], !7);
+
+    face.update(StencilOp::Zero, &mut plane, 0b11);
+    assert_eq!(&plane[..2], &[0, 0]);
+}
+
+#[test]
+fn write_mask() {
+    let mut plane = [0xF0u8; 64];
+    let face = StencilFace{reference: 0x0F, write_mask: 0x03, .. StencilFace::new()};
+
+    face.update(StencilOp::Replace, &mut plane, 0b1);
+    assert_eq!(plane[0], 0xF3);
+    assert_eq!(plane[1], 0xF0);
+}
+
+struct Solid(Rgba<u8>);
+
+impl Fragment<[f32; 4]> for Solid {
+    type Color = Rgba<u8>;
+
+    fn fragment(&self, _: [f32; 4]) -> Rgba<u8> { self.0 }
+}
+
+fn quad() -> Vec<Triangle<[f32; 4]>> {
+    vec![Triangle::new([-1., -1., 0., 1.], [1., -1., 0., 1.], [1., 1., 0., 1.]),
+         Triangle::new([-1., -1., 0., 1.], [1., 1., 0., 1.], [-1., 1., 0., 1.])]
+}
+
+#[test]
+fn stencil_mask() {
+    let mut frame = Frame::with_format(32, 32, FrameFormat { stencil: true, .. FrameFormat::new() },
+                                       Rgba([0u8, 0, 0, 255]));
+
+    // mark the lower right half, then only draw where it is marked
+    let mark = RasterState {
+        stencil: Some(Stencil::new(StencilFace { pass: StencilOp::Replace, reference: 1, .. StencilFace::new() })),
+        depth: DepthFunc::Always,
+        .. RasterState::new()
+    };
+    frame.raster_with(&mark, quad().into_iter().take(1), Solid(Rgba([0, 0, 0, 255])));
+
+    let test = RasterState {
+        stencil: Some(Stencil::new(StencilFace { func: DepthFunc::Equal, reference: 1, .. StencilFace::new() })),
+        depth: DepthFunc::Always,
+        .. RasterState::new()
+    };
+    frame.raster_with(&test, quad().into_iter(), Solid(Rgba([255, 0, 0, 255])));
+
+    let img = frame.to_image();
+    assert_eq!(*img.get_pixel(30, 30), Rgba([255, 0, 0, 255]));
+    assert_eq!(*img.get_pixel(1, 1), Rgba([0, 0, 0, 255]));
+}
+
+#[test]
+fn stencil_packed_with_depth() {
+    let format = FrameFormat { stencil: true, depth: DepthFormat::Unorm24, .. FrameFormat::new() };
+    let mut frame = Frame::with_format(32, 32, format, Rgba([0u8, 0, 0, 255]));
+
+    // mark the lower right half, writing both the depth and the stencil
+    let mark = RasterState {
+        stencil: Some(Stencil::new(StencilFace { pass: StencilOp::Replace, reference: 1, .. StencilFace::new() })),
+        .. RasterState::new()
+    };
+    frame.raster_with(&mark, quad().into_iter().take(1), Solid(Rgba([0, 0, 0, 255])));
+
+    let test = RasterState {
+        stencil: Some(Stencil::new(StencilFace { func: DepthFunc::Equal, reference: 1, .. StencilFace::new() })),
+        depth: DepthFunc::Always,
+        depth_write: false,
+        .. RasterState::new()
+    };
+    frame.raster_with(&test, quad().into_iter(), Solid(Rgba([255, 0, 0, 255])));
+
+    let img = frame.to_image();
+    assert_eq!(*img.get_pixel(30, 30), Rgba([255, 0, 0, 255]));
+    assert_eq!(*img.get_pixel(1, 1), Rgba([0, 0, 0, 255]));
+
+    // the stencil left the depth that it shares a word with alone
+    let depth = frame.depth_to_vec(DepthOutput::Window(DepthRange::NegativeOneToOne));
+    assert!((depth[30 * 32 + 30] - 0.5).abs() < 1e-6);
+    assert_eq!(depth[32 + 1], 1.);
+}
+
+#[test]
+#[should_panic]
+fn stencil_without_plane() {
+    let mut frame = Frame::new(32, 32, Rgba([0u8, 0, 0, 255]));
+    let state = RasterState {
+        stencil: Some(Stencil::new(StencilFace::new())),
+        .. RasterState::new()
+    };
+    frame.raster_with(&state, quad().into_iter(), Solid(Rgba([255, 0, 0, 255])));
+}
+
+struct Discard;
+
+impl ContextFragment<[f32; 4]> for Discard {
+    type Color = Rgba<u8>;
+
+    fn fragment_with(&self, _: [f32; 4], _: &FragmentContext<[f32; 4]>) -> Option<Rgba<u8>> { None }
+}
+
+#[test]
+fn discard_keeps_stencil() {
+    let mut frame = Frame::with_format(32, 32, FrameFormat { stencil: true, .. FrameFormat::new() },
+                                       Rgba([0u8, 0, 0, 255]));
+
+    // every pixel fails the stencil test and would be replaced, but
+    // only the fragments that are not discarded update the stencil
+    let fail = RasterState {
+        stencil: Some(Stencil::new(StencilFace {
+            func: DepthFunc::Never,
+            fail: StencilOp::Replace,
+            reference: 1,
+            .. StencilFace::new()
+        })),
+        depth: DepthFunc::Always,
+        .. RasterState::new()
+    };
+    frame.raster_with(&fail, quad().into_iter(), Discard);
+    frame.raster_with(&fail, quad().into_iter().take(1), Solid(Rgba([0, 0, 0, 255])));
+
+    let test = RasterState {
+        stencil: Some(Stencil::new(StencilFace { func: DepthFunc::Equal, reference: 1, .. StencilFace::new() })),
+        depth: DepthFunc::Always,
+        .. RasterState::new()
+    };
+    frame.raster_with(&test, quad().into_iter(), Solid(Rgba([255, 0, 0, 255])));
+
+    let img = frame.to_image();
+    assert_eq!(*img.get_pixel(30, 30), Rgba([255, 0, 0, 255]));
+    assert_eq!(*img.get_pixel(1, 1), Rgba([0, 0, 0, 255]));
+}
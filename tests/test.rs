@@ -6,8 +6,10 @@ extern crate obj;
 
 use std::path::{self, Path};
 use std::fs::File;
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
 
-use rusterize::{Frame, Flat, Fragment};
+use rusterize::{Frame, FrameFormat, Flat, Interpolate, Fragment, ContextFragment, FragmentContext, WideFragment, Derivatives, FrameTexture, Sampler, Filter};
+use rusterize::f32x8::f32x8x8;
 use cgmath::*;
 use genmesh::generators;
 use genmesh::{Triangulate, MapToVertices, Quad};
@@ -95,6 +97,51 @@ fn plane_fill() {
     check("plane_fill", frame);
 }
 
+#[test]
+fn plane_fill_rect() {
+    use image::GenericImage;
+
+    let mut frame = Frame::new(1000, 700, Rgba([0u8, 0, 0, 0]));
+    let cube = generators::Plane::new()
+        .triangulate()
+        .vertex(|v| proj().mul_v(&Vector4::new(v.0, v.1, 0., 1.)).into_fixed());
+
+    frame.raster(cube, SetValue(Rgba([255, 255, 255, 255])));
+    let img = frame.to_image();
+
+    assert_eq!(img.width(), 1000);
+    assert_eq!(img.height(), 700);
+    for &(x, y) in [(1, 1), (998, 1), (1, 698), (998, 698), (500, 350)].iter() {
+        assert_eq!(img.get_pixel(x, y), Rgba([255, 255, 255, 255]));
+    }
+}
+
+#[test]
+fn plane_fill_empty() {
+    // frames without any pixels have no tiles to draw to
+    for &(w, h) in [(0, 0), (64, 0), (0, 64)].iter() {
+        let mut frame = Frame::new(w, h, Rgba([0u8, 0, 0, 0]));
+        let cube = generators::Plane::new()
+            .triangulate()
+            .vertex(|v| proj().mul_v(&Vector4::new(v.0, v.1, 0., 1.)).into_fixed());
+
+        frame.raster(cube, SetValue(Rgba([255, 255, 255, 255])));
+        assert_eq!(frame.to_image().into_raw().len(), 0);
+    }
+}
+
+#[test]
+fn plane_fill_huge() {
+    // a triangle far outside of the frame that covers all of it, without
+    // the guard band its fixed-point edges would overflow
+    let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 0]));
+    let t = genmesh::Triangle::new([-1e6, -1e6, 0., 1.], [3e6, -1e6, 0., 1.], [-1e6, 3e6, 0., 1.]);
+
+    frame.raster(Some(t).into_iter(), SetValue(Rgba([255, 255, 255, 255])));
+    let img = frame.to_image();
+    assert!(img.pixels().all(|p| *p == Rgba([255, 255, 255, 255])));
+}
+
 #[test]
 fn plane_overfill() {
     let mut frame = Frame::new(SIZE, SIZE, Rgba([0u8, 0, 0, 0]));
@@ -140,6 +187,27 @@ fn plane_front_back() {
     check("plane_front_back", frame);
 }
 
+#[test]
+fn plane_front_back_reversed_z() {
+    use rusterize::{RasterState, reversed_z};
+
+    // the same scene as plane_front_back with the depth test flipped
+    let state = RasterState::reversed_z();
+    let format = FrameFormat { reversed_z: true, .. FrameFormat::new() };
+    let mut frame = Frame::with_format(SIZE, SIZE, format, Rgba([0u8, 0, 0, 0]));
+
+    let cube = generators::Plane::new()
+        .triangulate()
+        .vertex(|v| reversed_z(proj()).mul_v(&Vector4::new(v.0, v.1, 1., 1.)).into_fixed());
+    frame.raster_with(&state, cube, SetValue(Rgba([255, 255, 255, 255])));
+
+    let cube = generators::Plane::new()
+        .triangulate()
+        .vertex(|v| reversed_z(proj()).mul_v(&Vector4::new(v.0, v.1, 0., 1.)).into_fixed());
+    frame.raster_with(&state, cube, SetValue(Rgba([128, 128, 128, 255])));
+    check("plane_front_back", frame);
+}
+
 #[test]
 fn cube() {
     use genmesh::Triangle;
@@ -239,6 +307,38 @@ fn triangle_flat() {
     check("triangle_flat", frame);
 }
 
+#[test]
+fn triangle_perspective() {
+    use genmesh::Triangle;
+
+    // the right side of the triangle is three times further away
+    let triangle = [Triangle::new(
+        ([-1., -1., 0., 1.], 0.),
+        ([ 3., -3., 0., 3.], 1.),
+        ([ 3.,  3., 0., 3.], 1.),
+    )];
+
+    #[derive(Clone)]
+    struct V;
+
+    impl Fragment<([f32; 4], f32)> for V {
+        type Color = Rgba<u8>;
+
+        fn fragment(&self, (_, v) : ([f32; 4], f32)) -> Rgba<u8> {
+            Rgba([(v * 255.) as u8, 0, 0, 255])
+        }
+    }
+
+    let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 0]));
+    frame.raster(triangle.iter().map(|x| *x), V);
+
+    // halfway across the screen the attribute is a quarter of the way,
+    // interpolating it in screen space would give a half
+    let img = frame.to_image();
+    let v = img.get_pixel(32, 47).0[0] as i32;
+    assert!((v - 63).abs() <= 1, "{} is not perspective correct", v);
+}
+
 #[test]
 fn monkey() {
     let obj = obj::load(&path::Path::new("test_assets/monkey.obj")).unwrap();
@@ -327,3 +427,726 @@ fn plane_checker() {
     check("plane_checker", frame);
 }
 
+
+#[test]
+fn triangle_multisample() {
+    use genmesh::Triangle;
+
+    let triangle = [Triangle::new([-1.,  -1., 0., 1.],
+                                  [ 1.,  -1., 0., 1.],
+                                  [ 1.,   1., 0., 1.])];
+
+    let mut frame = Frame::new_multisample(64, 64, 4, Rgba([0u8, 0, 0, 255]));
+    frame.raster(triangle.iter().map(|x| *x), SetValue(Rgba([255, 255, 255, 255])));
+    let img = frame.to_image();
+
+    // the diagonal edge is partially covered
+    let edge = img.pixels().filter(|p| p.0[0] > 0 && p.0[0] < 255).count();
+    assert!(edge > 0);
+    assert!(img.pixels().any(|p| p.0[0] == 255));
+    assert!(img.pixels().any(|p| p.0[0] == 0));
+}
+
+#[test]
+#[should_panic]
+fn multisample_unsupported_count() {
+    Frame::new_multisample(64, 64, 3, Rgba([0u8, 0, 0, 255]));
+}
+
+#[test]
+#[should_panic]
+fn map_different_samples() {
+    let mut src = Frame::custom(64, 64, FrameFormat { samples: 4, .. FrameFormat::new() }, Count(0));
+    let mut dst = Frame::new(64, 64, Rgba([0u8, 0, 0, 255]));
+    dst.map(&mut src, ShowCount);
+}
+
+#[test]
+fn shared_edge_watertight() {
+    use rusterize::{RasterState, Blend, DepthFunc};
+
+    let state = RasterState {
+        depth: DepthFunc::Always,
+        blend: Some(Blend::additive()),
+        .. RasterState::new()
+    };
+
+    // the shared edges of the subdivided plane cross pixel centres
+    let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 0]));
+    let plane = generators::Plane::subdivide(4, 4)
+        .triangulate()
+        .vertex(|v| Vector4::new(v.0 * 0.5, v.1 * 0.5, 0., 1.).into_fixed());
+    frame.raster_with(&state, plane, SetValue(Rgba([100, 100, 100, 100])));
+
+    let img = frame.to_image();
+    assert!(img.pixels().all(|p| p.0[0] == 0 || p.0[0] == 100));
+    assert!(img.pixels().any(|p| p.0[0] == 100));
+}
+
+#[test]
+fn plane_indexed() {
+    use std::sync::Arc;
+    use rusterize::Topology;
+
+    let vertices = Arc::new(vec![[-0.5, -0.5, 0., 1.],
+                                 [ 0.5, -0.5, 0., 1.],
+                                 [ 0.5,  0.5, 0., 1.],
+                                 [-0.5,  0.5, 0., 1.]]);
+
+    let mut list = Frame::new(SIZE, SIZE, Rgba([0u8, 0, 0, 0]));
+    list.raster_indexed(vertices.clone(), &[0, 1, 2, 2, 3, 0], Topology::TriangleList,
+                        SetValue(Rgba([255, 255, 255, 255])));
+
+    let mut fan = Frame::new(SIZE, SIZE, Rgba([0u8, 0, 0, 0]));
+    fan.raster_indexed(vertices.clone(), &[0, 1, 2, 3], Topology::TriangleFan,
+                       SetValue(Rgba([255, 255, 255, 255])));
+
+    let mut strip = Frame::new(SIZE, SIZE, Rgba([0u8, 0, 0, 0]));
+    strip.raster_indexed(vertices.clone(), &[0, 1, 3, 2], Topology::TriangleStrip,
+                         SetValue(Rgba([255, 255, 255, 255])));
+
+    let list = list.to_image().into_raw();
+    assert!(list.iter().any(|&p| p == 255));
+    assert!(list == fan.to_image().into_raw());
+    assert!(list == strip.to_image().into_raw());
+}
+
+#[test]
+#[should_panic]
+fn plane_indexed_out_of_range() {
+    use std::sync::Arc;
+    use rusterize::Topology;
+
+    let vertices = Arc::new(vec![[-0.5, -0.5, 0., 1.],
+                                 [ 0.5, -0.5, 0., 1.],
+                                 [ 0.5,  0.5, 0., 1.]]);
+
+    let mut frame = Frame::new(SIZE, SIZE, Rgba([0u8, 0, 0, 0]));
+    frame.raster_indexed(vertices, &[0, 1, 3], Topology::TriangleList,
+                         SetValue(Rgba([255, 255, 255, 255])));
+}
+
+/// An attribute that counts how often it is cloned
+#[derive(Debug)]
+struct Counted(f32);
+
+static CLONES: AtomicUsize = ATOMIC_USIZE_INIT;
+
+impl Clone for Counted {
+    fn clone(&self) -> Counted {
+        CLONES.fetch_add(1, Ordering::SeqCst);
+        Counted(self.0)
+    }
+}
+
+impl Interpolate for Counted {
+    type Out = f32;
+
+    fn interpolate(src: genmesh::Triangle<&Counted>, w: [f32; 3]) -> f32 {
+        src.x.0 * w[0] + src.y.0 * w[1] + src.z.0 * w[2]
+    }
+}
+
+struct ShowCounted;
+
+impl Fragment<([f32; 4], f32)> for ShowCounted {
+    type Color = Rgba<u8>;
+
+    fn fragment(&self, (_, v): ([f32; 4], f32)) -> Rgba<u8> { Rgba([(v * 255.) as u8, 0, 0, 255]) }
+}
+
+#[test]
+fn plane_indexed_no_copies() {
+    use std::sync::Arc;
+    use rusterize::Topology;
+
+    let vertices = Arc::new(vec![([-1., -1., 0., 1.], Counted(1.)),
+                                 ([ 1., -1., 0., 1.], Counted(1.)),
+                                 ([ 1.,  1., 0., 1.], Counted(1.)),
+                                 ([-1.,  1., 0., 1.], Counted(1.))]);
+
+    // every tile of the frame shades the vertices without copying them
+    let mut frame = Frame::new(SIZE, SIZE, Rgba([0u8, 0, 0, 0]));
+    frame.raster_indexed(vertices, &[0, 1, 2, 2, 3, 0], Topology::TriangleList, ShowCounted);
+
+    let img = frame.to_image();
+    assert!(img.pixels().all(|p| p.0[0] == 255));
+    assert_eq!(CLONES.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn plane_vertex_shader() {
+    use std::sync::Arc;
+    use rusterize::{Topology, Vertex};
+
+    struct Scale(f32);
+
+    impl Vertex<[f32; 2]> for Scale {
+        type Out = [f32; 4];
+
+        fn vertex(&self, v: &[f32; 2]) -> ([f32; 4], [f32; 4]) {
+            let p = [v[0] * self.0, v[1] * self.0, 0., 1.];
+            (p, p)
+        }
+    }
+
+    let vertices = vec![[-1., -1.], [1., -1.], [1., 1.], [-1., 1.]];
+    let indices = vec![0, 1, 2, 2, 3, 0];
+
+    let mut shaded = Frame::new(SIZE, SIZE, Rgba([0u8, 0, 0, 0]));
+    shaded.draw(Arc::new(vertices.clone()), Arc::new(indices.clone()), Topology::TriangleList, Scale(0.5),
+                SetValue(Rgba([255, 255, 255, 255])));
+
+    let scaled: Vec<[f32; 4]> = vertices.iter().map(|v| [v[0] * 0.5, v[1] * 0.5, 0., 1.]).collect();
+    let mut expected = Frame::new(SIZE, SIZE, Rgba([0u8, 0, 0, 0]));
+    expected.raster_indexed(Arc::new(scaled), &indices, Topology::TriangleList,
+                            SetValue(Rgba([255, 255, 255, 255])));
+
+    assert!(shaded.to_image().into_raw() == expected.to_image().into_raw());
+}
+
+#[test]
+fn vertex_shader_outputs() {
+    use std::sync::Arc;
+    use rusterize::{Topology, Vertex};
+
+    // the outputs are only a colour, the position is returned on its own
+    struct Tint;
+
+    impl Vertex<[f32; 2]> for Tint {
+        type Out = Flat<[f32; 4]>;
+
+        fn vertex(&self, v: &[f32; 2]) -> ([f32; 4], Flat<[f32; 4]>) {
+            ([v[0], v[1], 0., 1.], Flat([1., 0., 0., 1.]))
+        }
+    }
+
+    struct ToRgba;
+
+    impl Fragment<[f32; 4]> for ToRgba {
+        type Color = Rgba<u8>;
+
+        fn fragment(&self, c: [f32; 4]) -> Rgba<u8> {
+            Rgba([(c[0] * 255.) as u8, (c[1] * 255.) as u8, (c[2] * 255.) as u8, (c[3] * 255.) as u8])
+        }
+    }
+
+    let vertices = Arc::new(vec![[-1., -1.], [1., -1.], [1., 1.], [-1., 1.]]);
+    let indices = Arc::new(vec![0, 1, 2, 2, 3, 0]);
+
+    let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 0]));
+    frame.draw(vertices, indices, Topology::TriangleList, Tint, ToRgba);
+
+    let img = frame.to_image();
+    assert!(img.pixels().all(|p| *p == Rgba([255, 0, 0, 255])));
+}
+
+#[test]
+fn lines_connected() {
+    use genmesh::Line;
+    use rusterize::{RasterState, Blend, DepthFunc};
+
+    let state = RasterState {
+        depth: DepthFunc::Always,
+        blend: Some(Blend::additive()),
+        .. RasterState::new()
+    };
+
+    // two x-major lines that share an end point
+    let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 0]));
+    let lines = vec![Line::new([-0.9, -0.3, 0., 1.], [0., 0.1, 0., 1.]),
+                     Line::new([0., 0.1, 0., 1.], [0.9, -0.2, 0., 1.])];
+    frame.raster_lines_with(&state, lines.into_iter(), SetValue(Rgba([100, 100, 100, 100])));
+
+    let img = frame.to_image();
+    assert!(img.pixels().all(|p| p.0[0] == 0 || p.0[0] == 100));
+    for x in 8..56 {
+        let lit = (0..64).filter(|&y| img.get_pixel(x, y).0[0] == 100).count();
+        assert_eq!(lit, 1);
+    }
+}
+
+#[test]
+fn line_diamond_exit() {
+    use genmesh::Line;
+
+    // pixel centers of a 16x16 frame are at px / 8 - 1
+    let ndc = |x: f32, y: f32| [x / 8. - 1., y / 8. - 1., 0., 1.];
+    let lit = |line: Line<[f32; 4]>| {
+        let mut frame = Frame::new(16, 16, Rgba([0u8, 0, 0, 0]));
+        frame.raster_lines(vec![line].into_iter(), SetValue(Rgba([255, 255, 255, 255])));
+        let img = frame.to_image();
+        let mut lit = Vec::new();
+        for y in 0..16 {
+            for x in 0..16 {
+                if img.get_pixel(x, 15 - y).0[0] == 255 {
+                    lit.push((x, y));
+                }
+            }
+        }
+        lit
+    };
+
+    // the pixel holding the end point is left to the next line
+    assert_eq!(lit(Line::new(ndc(1.7, 3.), ndc(6.3, 3.))),
+               vec![(2, 3), (3, 3), (4, 3), (5, 3)]);
+
+    // the line leaves the diamond of the pixel it starts in
+    assert_eq!(lit(Line::new(ndc(2.2, 2.2), ndc(6.4, 6.4))),
+               vec![(2, 2), (3, 3), (4, 4), (5, 5), (6, 6)]);
+}
+
+#[test]
+fn line_smooth() {
+    use genmesh::Line;
+    use rusterize::{RasterState, Blend};
+
+    let state = RasterState {
+        blend: Some(Blend::alpha()),
+        line_width: 3.,
+        line_smooth: true,
+        .. RasterState::new()
+    };
+
+    let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 255]));
+    let lines = vec![Line::new([-0.8, -0.7, 0., 1.], [0.8, 0.5, 0., 1.])];
+    frame.raster_lines_with(&state, lines.into_iter(), SetValue(Rgba([255, 255, 255, 255])));
+
+    let img = frame.to_image();
+    assert!(img.pixels().any(|p| p.0[0] == 255));
+    assert!(img.pixels().any(|p| p.0[0] > 0 && p.0[0] < 255));
+}
+
+struct PointCoord;
+
+impl ContextFragment<[f32; 4]> for PointCoord {
+    type Color = Rgba<u8>;
+
+    fn fragment_with(&self, _: [f32; 4], ctx: &FragmentContext<[f32; 4]>) -> Option<Rgba<u8>> {
+        ctx.point.map(|coord| Rgba([(coord[0] * 255.) as u8, (coord[1] * 255.) as u8, 0, 255]))
+    }
+}
+
+#[test]
+fn point_sprites() {
+    use rusterize::Point;
+
+    // one point in the middle of each quarter of the frame
+    let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 0]));
+    let points = vec![Point::new([-0.5, -0.5, 0., 1.], 4.),
+                      Point::new([ 0.5, -0.5, 0., 1.], 4.),
+                      Point::new([-0.5,  0.5, 0., 1.], 4.),
+                      Point::new([ 0.5,  0.5, 0., 1.], 4.)];
+    frame.raster_points(points.into_iter(), SetValue(Rgba([255, 255, 255, 255])));
+
+    let img = frame.to_image();
+    assert_eq!(img.pixels().filter(|p| p.0[0] == 255).count(), 4 * 16);
+}
+
+struct PointCutout;
+
+impl ContextFragment<[f32; 4]> for PointCutout {
+    type Color = Rgba<u8>;
+
+    fn fragment_with(&self, _: [f32; 4], ctx: &FragmentContext<[f32; 4]>) -> Option<Rgba<u8>> {
+        match ctx.point {
+            Some(coord) if coord[0] < 0.5 => None,
+            _ => Some(Rgba([255, 255, 255, 255]))
+        }
+    }
+}
+
+#[test]
+fn point_sprite_discard() {
+    use rusterize::Point;
+
+    // the left half of the sprite is discarded
+    let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 255]));
+    let points = vec![Point::new([0., 0., 0., 1.], 32.)];
+    frame.raster_points(points.into_iter(), PointCutout);
+
+    // the plane behind it only shows through where nothing wrote depth
+    let back = generators::Plane::new()
+        .triangulate()
+        .vertex(|v| Vector4::new(v.0, v.1, 0.5, 1.).into_fixed());
+    frame.raster(back, SetValue(Rgba([255, 0, 0, 255])));
+
+    let img = frame.to_image();
+    assert_eq!(img.get_pixel(20, 32).0, [255, 0, 0, 255]);
+    assert_eq!(img.get_pixel(44, 32).0, [255, 255, 255, 255]);
+}
+
+#[test]
+fn point_sprite_round() {
+    use rusterize::{Point, RasterState};
+
+    let state = RasterState {
+        point_round: true,
+        .. RasterState::new()
+    };
+
+    let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 0]));
+    let points = vec![Point::new([0., 0., 0., 1.], 32.)];
+    frame.raster_points_with(&state, points.into_iter(), PointCoord);
+
+    let img = frame.to_image();
+    let lit = img.pixels().filter(|p| p.0[3] == 255).count();
+    assert!(lit > 700 && lit < 32 * 32);
+
+    // the point coordinate increases to the right and upwards
+    let (left, right) = (img.get_pixel(20, 32), img.get_pixel(44, 32));
+    assert!(left.0[0] < right.0[0]);
+    let (top, bottom) = (img.get_pixel(32, 20), img.get_pixel(32, 44));
+    assert!(top.0[1] > bottom.0[1]);
+}
+
+#[test]
+fn plane_wireframe() {
+    use rusterize::{RasterState, PolygonMode};
+
+    let draw = |hide_diagonals| {
+        let state = RasterState {
+            polygon_mode: PolygonMode::Line,
+            hide_diagonals: hide_diagonals,
+            .. RasterState::new()
+        };
+
+        let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 0]));
+        let plane = generators::Plane::new()
+            .triangulate()
+            .vertex(|v| Vector4::new(v.0 * 0.5, v.1 * 0.5, 0., 1.).into_fixed());
+        frame.raster_with(&state, plane, SetValue(Rgba([255, 255, 255, 255])));
+        frame.to_image()
+    };
+
+    let interior = |img: &image::ImageBuffer<Rgba<u8>, Vec<u8>>| {
+        (20..44).flat_map(|y| (20..44).map(move |x| (x, y)))
+                .filter(|&(x, y)| img.get_pixel(x, y).0[0] == 255)
+                .count()
+    };
+
+    // the outline is drawn in both cases, the diagonal only if it is not hidden
+    let all = draw(false);
+    let outline = draw(true);
+    assert!(interior(&all) > 0);
+    assert_eq!(interior(&outline), 0);
+    assert_eq!(all.get_pixel(16, 32).0[0], 255);
+    assert_eq!(outline.get_pixel(16, 32).0[0], 255);
+}
+
+#[test]
+fn plane_wireframe_provoking_last() {
+    use std::sync::Arc;
+    use rusterize::{RasterState, PolygonMode, Assembly, Topology, ProvokingVertex};
+
+    let vertices = Arc::new(vec![[-0.5, -0.5, 0., 1.],
+                                 [ 0.5, -0.5, 0., 1.],
+                                 [ 0.5,  0.5, 0., 1.],
+                                 [-0.5,  0.5, 0., 1.]]);
+    let state = RasterState {
+        polygon_mode: PolygonMode::Line,
+        hide_diagonals: true,
+        .. RasterState::new()
+    };
+
+    // the triangles are rotated, the edge between 0 and 2 is still hidden
+    let mut assembly = Assembly::new(Topology::TriangleList);
+    assembly.provoking = ProvokingVertex::Last;
+    let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 0]));
+    frame.raster_indexed_with(&state, vertices, &[0, 1, 2, 2, 3, 0], assembly,
+                              SetValue(Rgba([255, 255, 255, 255])));
+
+    let img = frame.to_image();
+    let interior = (20..44).flat_map(|y| (20..44).map(move |x| (x, y)))
+                           .filter(|&(x, y)| img.get_pixel(x, y).0[0] == 255)
+                           .count();
+    assert_eq!(interior, 0);
+    assert_eq!(img.get_pixel(16, 32).0[0], 255);
+}
+
+struct Cutout;
+
+impl ContextFragment<([f32; 4], [f32; 2])> for Cutout {
+    type Color = Rgba<u8>;
+
+    fn fragment_with(&self, (_, uv): ([f32; 4], [f32; 2]),
+                     _: &FragmentContext<([f32; 4], [f32; 2])>) -> Option<Rgba<u8>> {
+        if uv[0] < 0.5 { None } else { Some(Rgba([255, 255, 255, 255])) }
+    }
+}
+
+#[test]
+fn fragment_discard() {
+    let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 255]));
+
+    // the left half of the front plane is discarded
+    let front = vec![Quad::new(([-1., -1., 0., 1.], [0., 0.]),
+                               ([ 1., -1., 0., 1.], [1., 0.]),
+                               ([ 1.,  1., 0., 1.], [1., 1.]),
+                               ([-1.,  1., 0., 1.], [0., 1.]))];
+    frame.raster(front.into_iter().triangulate(), Cutout);
+
+    // the plane behind it only shows through where nothing wrote depth
+    let back = generators::Plane::new()
+        .triangulate()
+        .vertex(|v| Vector4::new(v.0, v.1, 0.5, 1.).into_fixed());
+    frame.raster(back, SetValue(Rgba([255, 0, 0, 255])));
+
+    let img = frame.to_image();
+    assert_eq!(img.get_pixel(8, 32).0, [255, 0, 0, 255]);
+    assert_eq!(img.get_pixel(56, 32).0, [255, 255, 255, 255]);
+}
+
+struct Gradient;
+
+impl Fragment<([f32; 4], [f32; 2])> for Gradient {
+    type Color = Rgba<u8>;
+
+    fn fragment(&self, (_, uv): ([f32; 4], [f32; 2])) -> Rgba<u8> {
+        Rgba([(uv[0] * 255.) as u8, (uv[1] * 255.) as u8, 0, 255])
+    }
+}
+
+impl WideFragment<([f32x8x8; 4], [f32x8x8; 2])> for Gradient {
+    type Color = Rgba<u8>;
+
+    fn fragment_wide(&self, (_, uv): ([f32x8x8; 4], [f32x8x8; 2]),
+                     mask: u64, color: &mut [Rgba<u8>; 64]) -> u64 {
+        let (u, v) = ((uv[0] * 255.).to_array(), (uv[1] * 255.).to_array());
+        for i in (0..64).filter(|&i| mask & (1 << i) != 0) {
+            color[i] = Rgba([u[i] as u8, v[i] as u8, 0, 255]);
+        }
+        mask
+    }
+}
+
+#[test]
+fn plane_wide() {
+    // the varying w gives the plane perspective
+    let plane = || {
+        let v = vec![Quad::new(([-1., -1., 0., 1.], [0., 0.]),
+                               ([ 1., -1., 0., 1.], [1., 0.]),
+                               ([ 2.,  2., 0., 2.], [1., 1.]),
+                               ([-2.,  2., 0., 2.], [0., 1.]))];
+        v.into_iter()
+         .triangulate()
+         .vertex(|(p, t)| (proj().mul_v(&Vector4::new(p[0], p[1], p[2], p[3])).into_fixed(), t))
+    };
+
+    // shading a tile at a time gives the same result as shading each pixel
+    let mut scalar = Frame::new(128, 128, Rgba([0u8, 0, 0, 0]));
+    scalar.raster(plane(), Gradient);
+    let mut wide = Frame::new(128, 128, Rgba([0u8, 0, 0, 0]));
+    wide.raster_wide(plane(), Gradient);
+
+    let (scalar, wide) = (scalar.to_image(), wide.to_image());
+    assert!(scalar.pixels().any(|p| p.0[3] == 255));
+    for (a, b) in scalar.pixels().zip(wide.pixels()) {
+        for c in 0..4 {
+            assert!((a.0[c] as i32 - b.0[c] as i32).abs() <= 1);
+        }
+    }
+}
+
+struct Slope;
+
+impl ContextFragment<([f32; 4], [f32; 2])> for Slope {
+    type Color = Rgba<u8>;
+
+    fn fragment_with(&self, _: ([f32; 4], [f32; 2]),
+                     ctx: &FragmentContext<([f32; 4], [f32; 2])>) -> Option<Rgba<u8>> {
+        let (_, ddx) = ctx.derivatives.ddx();
+        let (_, ddy) = ctx.derivatives.ddy();
+        let near = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        let ok = near(ddx[0], 2. / 64.) && near(ddx[1], 0.) &&
+                 near(ddy[0], 0.) && near(ddy[1], 2. / 64.);
+        Some(if ok { Rgba([255, 255, 255, 255]) } else { Rgba([255, 0, 0, 255]) })
+    }
+}
+
+#[test]
+fn derivatives() {
+    use genmesh::Triangle;
+
+    // the attribute is the position, so it changes by a pixel per pixel
+    // even in the quads along the edges that are only partly covered
+    let triangle = [Triangle::new(([-0.9, -0.7, 0., 1.], [-0.9, -0.7]),
+                                  ([ 0.8, -0.9, 0., 1.], [ 0.8, -0.9]),
+                                  ([ 0.1,  0.9, 0., 1.], [ 0.1,  0.9]))];
+
+    let mut frame = Frame::new(64, 64, Rgba([0u8, 0, 0, 0]));
+    frame.raster(triangle.iter().map(|x| *x), Slope);
+    let img = frame.to_image();
+
+    assert!(img.pixels().any(|p| p.0[0] == 255));
+    assert!(img.pixels().all(|p| p.0[0] == p.0[1]));
+}
+
+struct CopyFrame(FrameTexture<Rgba<u8>>);
+
+impl Fragment<([f32; 4], [f32; 2])> for CopyFrame {
+    type Color = Rgba<u8>;
+
+    fn fragment(&self, (_, uv): ([f32; 4], [f32; 2])) -> Rgba<u8> {
+        let sampler = Sampler { filter: Filter::Nearest, .. Sampler::new() };
+        sampler.sample(self.0.get().expect("the texture is bound"), uv)
+    }
+}
+
+#[test]
+fn render_to_texture() {
+    use genmesh::Triangle;
+
+    let mut first = Frame::new(64, 64, Rgba([255u8, 0, 0, 255]));
+    let triangle = [Triangle::new([-0.5, -0.5, 0., 1.],
+                                  [ 0.5, -0.5, 0., 1.],
+                                  [ 0.,   0.5, 0., 1.])];
+    first.raster(triangle.iter().map(|x| *x), SetValue(Rgba([255, 255, 255, 255])));
+    let texture = first.texture();
+
+    // the uvs are offset by half a texel to sample the middle of each texel
+    let half = 0.5 / 64.;
+    let quad = vec![Quad::new(([-1., -1., 0., 1.], [half,      half]),
+                              ([ 1., -1., 0., 1.], [1. + half, half]),
+                              ([ 1.,  1., 0., 1.], [1. + half, 1. + half]),
+                              ([-1.,  1., 0., 1.], [half,      1. + half]))];
+
+    let mut second = Frame::new(64, 64, Rgba([0u8, 0, 0, 0]));
+    second.bind(&texture);
+    second.raster(quad.into_iter().triangulate(), CopyFrame(texture.clone()));
+
+    let (a, b) = (first.to_image(), second.to_image());
+    assert!(a.pixels().any(|p| p.0[1] == 255));
+    assert!(a.into_raw() == b.into_raw());
+
+    // the texture stays readable once it is built
+    assert_eq!(texture.wait().width(), 64);
+    assert!(texture.get().is_some());
+}
+
+type GBuffer = (Rgba<u8>, Rgba<u8>);
+
+struct Targets;
+
+impl Fragment<[f32; 4]> for Targets {
+    type Color = GBuffer;
+
+    fn fragment(&self, _: [f32; 4]) -> GBuffer {
+        (Rgba([255, 0, 0, 255]), Rgba([0, 255, 0, 128]))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Target(usize);
+
+impl rusterize::Mapping<GBuffer> for Target {
+    type Out = Rgba<u8>;
+
+    fn mapping(&self, p: GBuffer) -> Rgba<u8> {
+        if self.0 == 0 { p.0 } else { p.1 }
+    }
+}
+
+#[test]
+fn multiple_render_targets() {
+    use rusterize::{RasterState, Blend};
+    use genmesh::Triangle;
+
+    // only the second target is blended
+    let state = RasterState {
+        blend_targets: Some([None, Some(Blend::alpha()), None, None]),
+        .. RasterState::new()
+    };
+
+    let triangle = [Triangle::new([-1., -1., 0., 1.],
+                                  [ 1., -1., 0., 1.],
+                                  [ 1.,  1., 0., 1.])];
+
+    let mut frame = Frame::new(32, 32, (Rgba([0u8, 0, 0, 255]), Rgba([0u8, 0, 255, 255])));
+    frame.raster_with(&state, triangle.iter().map(|x| *x), Targets);
+
+    let mut first = Frame::new(32, 32, Rgba([0u8, 0, 0, 0]));
+    first.map(&mut frame, Target(0));
+    let mut second = Frame::new(32, 32, Rgba([0u8, 0, 0, 0]));
+    second.map(&mut frame, Target(1));
+    let (first, second) = (first.to_image(), second.to_image());
+
+    // the lower right half of the frame is covered
+    assert_eq!(*first.get_pixel(30, 30), Rgba([255, 0, 0, 255]));
+    assert_eq!(*first.get_pixel(1, 1), Rgba([0, 0, 0, 255]));
+    assert_eq!(*second.get_pixel(1, 1), Rgba([0, 0, 255, 255]));
+
+    let p = second.get_pixel(30, 30).0;
+    assert!(p[0] == 0 && p[3] == 255);
+    assert!(p[1] >= 127 && p[1] <= 129);
+    assert!(p[2] >= 126 && p[2] <= 128);
+}
+
+/// A colour that can not be blended by the fixed function blend state
+#[derive(Clone, Copy)]
+struct Count(u8);
+
+struct Overdraw;
+
+impl Fragment<[f32; 4]> for Overdraw {
+    type Color = Count;
+
+    fn fragment(&self, _: [f32; 4]) -> Count { Count(1) }
+
+    fn blend(&self, dst: Count, new: Count) -> Count { Count(dst.0 + new.0) }
+}
+
+#[derive(Clone, Copy)]
+struct ShowCount;
+
+impl rusterize::Mapping<Count> for ShowCount {
+    type Out = Rgba<u8>;
+
+    fn mapping(&self, p: Count) -> Rgba<u8> { Rgba([p.0, 0, 0, 255]) }
+}
+
+#[test]
+fn custom_color() {
+    use rusterize::{RasterState, DepthFunc};
+    use genmesh::Triangle;
+
+    let state = RasterState {
+        depth: DepthFunc::Always,
+        .. RasterState::new()
+    };
+
+    // the lower right half of the frame is drawn twice
+    let triangle = [Triangle::new([-1., -1., 0., 1.],
+                                  [ 1., -1., 0., 1.],
+                                  [ 1.,  1., 0., 1.])];
+
+    let mut frame = Frame::custom(32, 32, FrameFormat::new(), Count(0));
+    frame.raster_with(&state, triangle.iter().map(|x| *x), Overdraw);
+    frame.raster_with(&state, triangle.iter().map(|x| *x), Overdraw);
+
+    let mut shown = Frame::new(32, 32, Rgba([0u8, 0, 0, 0]));
+    shown.map(&mut frame, ShowCount);
+    let img = shown.to_image();
+
+    assert_eq!(img.get_pixel(30, 30).0[0], 2);
+    assert_eq!(img.get_pixel(1, 1).0[0], 0);
+}
+
+#[test]
+#[should_panic]
+fn custom_color_blend() {
+    use genmesh::Triangle;
+    use rusterize::{RasterState, Blend};
+
+    // a colour that is not `Planes` has no fixed function blend state
+    let state = RasterState {
+        blend: Some(Blend::additive()),
+        .. RasterState::new()
+    };
+    let triangle = [Triangle::new([-1., -1., 0., 1.],
+                                  [ 1., -1., 0., 1.],
+                                  [ 1.,  1., 0., 1.])];
+
+    let mut frame = Frame::custom(32, 32, FrameFormat::new(), Count(0));
+    frame.raster_with(&state, triangle.iter().map(|x| *x), Overdraw);
+}
@@ -0,0 +1,219 @@
+extern crate rusterize;
+extern crate image;
+
+use image::{ImageBuffer, Rgba};
+use rusterize::{Frame, Texture2D, Texture2DArray, TextureCube, Texture3D, Sampler, Filter, Wrap};
+use rusterize::f32x8::f32x8x8;
+
+fn gray(v: f32) -> [f32; 4] { [v, v, v, 1.] }
+
+#[test]
+fn mip_chain() {
+    let texture = Texture2D::new(4, 2, (0..8).map(|i| gray(i as f32)).collect());
+    assert_eq!(texture.levels(), 3);
+    assert_eq!((texture.level(1).width, texture.level(1).height), (2, 1));
+    assert_eq!((texture.level(2).width, texture.level(2).height), (1, 1));
+
+    // each texel is the average of a 2x2 block of the level above
+    assert_eq!(texture.level(1).data, vec![gray(2.5), gray(4.5)]);
+    assert_eq!(texture.level(2).data, vec![gray(3.5)]);
+}
+
+#[test]
+fn mip_chain_odd() {
+    let texture = Texture2D::new(5, 3, (0..15).map(|i| gray(i as f32)).collect());
+    assert_eq!((texture.level(1).width, texture.level(1).height), (2, 1));
+
+    // every texel of an odd row contributes, the middle one to both halves
+    let near = |a: [f32; 4], b: f32| (a[0] - b).abs() < 1e-4;
+    let level = &texture.level(1).data;
+    assert!(near(level[0], 5.8));
+    assert!(near(level[1], 8.2));
+    assert!(near(texture.level(2).data[0], 7.));
+
+    let slices: Vec<_> = (0..3).map(|z| Texture2D::new(1, 1, vec![gray(z as f32)])).collect();
+    let volume = Texture3D::from_slices(&slices);
+    assert!(near(volume.level(1).data[0], 1.));
+}
+
+#[test]
+fn wrap() {
+    assert_eq!((-5..5).map(|i| Wrap::Repeat.apply(i, 3)).collect::<Vec<_>>(),
+               vec![1, 2, 0, 1, 2, 0, 1, 2, 0, 1]);
+    assert_eq!((-5..5).map(|i| Wrap::Clamp.apply(i, 3)).collect::<Vec<_>>(),
+               vec![0, 0, 0, 0, 0, 0, 1, 2, 2, 2]);
+    assert_eq!((-5..5).map(|i| Wrap::Mirror.apply(i, 3)).collect::<Vec<_>>(),
+               vec![1, 2, 2, 1, 0, 0, 1, 2, 2, 1]);
+}
+
+#[test]
+fn nearest_and_bilinear() {
+    let texture = Texture2D::new(2, 1, vec![gray(0.), gray(1.)]);
+    let nearest = Sampler {
+        filter: Filter::Nearest,
+        wrap: [Wrap::Clamp, Wrap::Clamp],
+        .. Sampler::new()
+    };
+    let bilinear = Sampler {
+        filter: Filter::Bilinear,
+        .. nearest
+    };
+
+    assert_eq!(nearest.sample(&texture, [0.4, 0.5]), gray(0.));
+    assert_eq!(nearest.sample(&texture, [0.6, 0.5]), gray(1.));
+    assert_eq!(bilinear.sample(&texture, [0.5, 0.5]), gray(0.5));
+    assert_eq!(bilinear.sample(&texture, [0.0, 0.5]), gray(0.));
+
+    // repeating blends the last texel with the first
+    let repeat = Sampler { wrap: [Wrap::Repeat, Wrap::Repeat], .. bilinear };
+    assert_eq!(repeat.sample(&texture, [0.0, 0.5]), gray(0.5));
+}
+
+#[test]
+fn trilinear() {
+    let texture = Texture2D::new(2, 2, vec![gray(0.), gray(1.), gray(1.), gray(0.)]);
+    let sampler = Sampler::new();
+
+    assert_eq!(sampler.sample_lod(&texture, [0.25, 0.25], 0.), gray(0.));
+    assert_eq!(sampler.sample_lod(&texture, [0.25, 0.25], 1.), gray(0.5));
+    assert_eq!(sampler.sample_lod(&texture, [0.25, 0.25], 0.5), gray(0.25));
+
+    // a texel per pixel is level 0, two texels per pixel is level 1
+    assert_eq!(texture.lod([0.5, 0.], [0., 0.5]), 0.);
+    assert_eq!(texture.lod([1., 0.], [0., 0.25]), 1.);
+}
+
+#[test]
+fn from_image_and_frame() {
+    let mut img = ImageBuffer::new(2, 2);
+    img.put_pixel(0, 0, Rgba([255u8, 0, 0, 255]));
+    img.put_pixel(1, 0, Rgba([0u8, 255, 0, 255]));
+    img.put_pixel(0, 1, Rgba([0u8, 0, 255, 255]));
+    img.put_pixel(1, 1, Rgba([255u8, 255, 255, 255]));
+
+    // the top row of the image is the top row of the texture
+    let texture = Texture2D::from_image(&img);
+    let sampler = Sampler { filter: Filter::Nearest, .. Sampler::new() };
+    assert_eq!(sampler.sample(&texture, [0.25, 0.75]), Rgba([255, 0, 0, 255]));
+    assert_eq!(sampler.sample(&texture, [0.25, 0.25]), Rgba([0, 0, 255, 255]));
+
+    let mut frame = Frame::new(40, 20, Rgba([10u8, 20, 30, 255]));
+    let texture = frame.to_texture();
+    assert_eq!((texture.width(), texture.height()), (40, 20));
+    assert_eq!(texture.levels(), 6);
+    assert_eq!(sampler.sample(&texture, [0.9, 0.9]), Rgba([10, 20, 30, 255]));
+}
+
+#[test]
+#[should_panic]
+fn empty_frame() {
+    let mut frame = Frame::new(0, 4, Rgba([0u8, 0, 0, 255]));
+    frame.texture();
+}
+
+#[test]
+fn lod_bias_and_clamp() {
+    let texture = Texture2D::new(2, 2, vec![gray(0.), gray(1.), gray(1.), gray(0.)]);
+    let uv = [0.25, 0.25];
+
+    let biased = Sampler { lod_bias: 1., .. Sampler::new() };
+    assert_eq!(biased.sample_lod(&texture, uv, 0.), gray(0.5));
+
+    let min = Sampler { min_lod: 0.5, .. Sampler::new() };
+    assert_eq!(min.sample_lod(&texture, uv, 0.), gray(0.25));
+
+    let max = Sampler { max_lod: 0., .. Sampler::new() };
+    assert_eq!(max.sample_lod(&texture, uv, 1.), gray(0.));
+}
+
+#[test]
+fn anisotropic() {
+    // horizontal stripes a texel high
+    let data = (0..64*64).map(|i| gray(((i / 64) % 2) as f32)).collect();
+    let texture = Texture2D::new(64, 64, data);
+
+    // the footprint is four texels wide and one texel high
+    let uv = [0.5, 10.5 / 64.];
+    let (ddx, ddy) = ([4. / 64., 0.], [0., 1. / 64.]);
+
+    // isotropic filtering picks the level where the stripes are averaged out
+    let isotropic = Sampler::new();
+    assert_eq!(isotropic.sample_grad(&texture, uv, ddx, ddy), gray(0.5));
+
+    // taking four samples along u keeps the stripes sharp
+    let anisotropic = Sampler { max_anisotropy: 16, .. Sampler::new() };
+    assert_eq!(anisotropic.sample_grad(&texture, uv, ddx, ddy), gray(0.));
+
+    // limiting the samples falls back to a blurrier level
+    let limited = Sampler { max_anisotropy: 2, .. Sampler::new() };
+    assert_eq!(limited.sample_grad(&texture, uv, ddx, ddy), gray(0.5));
+
+    // a footprint without height takes as many samples as it may
+    assert_eq!(anisotropic.sample_grad(&texture, uv, ddx, [0., 0.]), gray(0.));
+}
+
+#[test]
+fn sample_tile() {
+    let texture = Texture2D::new(2, 1, vec![gray(0.), gray(1.)]);
+    let sampler = Sampler { filter: Filter::Nearest, .. Sampler::new() };
+
+    let mut out = [gray(-1.); 64];
+    let uv = [f32x8x8::broadcast(0.75), f32x8x8::broadcast(0.5)];
+    sampler.sample_tile(&texture, uv, 0xff, &mut out);
+
+    assert!(out[..8].iter().all(|&c| c == gray(1.)));
+    assert!(out[8..].iter().all(|&c| c == gray(-1.)));
+}
+
+#[test]
+fn cube_from_frames() {
+    let faces = (0..6).map(|i| Frame::new(4, 4, Rgba([i as u8 * 40, 0, 0, 255])).to_texture());
+    let cube = TextureCube::new(faces.collect());
+    let sampler = Sampler::new();
+
+    let dirs = [[2., 0.1, 0.], [-1., 0., 0.5], [0., 3., 0.], [0.2, -1., 0.], [0., 0., 1.], [0., -0.5, -1.]];
+    for (i, dir) in dirs.iter().enumerate() {
+        assert_eq!(sampler.sample_cube(&cube, *dir), Rgba([i as u8 * 40, 0, 0, 255]));
+    }
+}
+
+#[test]
+fn cube_seamless() {
+    let faces = (0..6).map(|i| Texture2D::new(2, 2, vec![gray(if i == 4 { 1. } else { 0. }); 4]));
+    let cube = TextureCube::new(faces.collect());
+    let sampler = Sampler::new();
+
+    assert_eq!(sampler.sample_cube(&cube, [1., 0., 0.]), gray(0.));
+
+    // right next to the edge between +x and +z the two faces are blended
+    let edge = sampler.sample_cube(&cube, [1., 0., 0.999]);
+    assert!(edge[0] > 0.4 && edge[0] < 0.6);
+    let edge = sampler.sample_cube(&cube, [0.999, 0., 1.]);
+    assert!(edge[0] > 0.4 && edge[0] < 0.6);
+}
+
+#[test]
+fn array() {
+    let layers = (0..3).map(|i| Texture2D::new(1, 1, vec![gray(i as f32)])).collect();
+    let array = Texture2DArray::new(layers);
+    let sampler = Sampler::new();
+
+    assert_eq!(sampler.sample_array(&array, [0.5, 0.5], 1.4), gray(1.));
+    assert_eq!(sampler.sample_array(&array, [0.5, 0.5], 5.), gray(2.));
+    assert_eq!(sampler.sample_array(&array, [0.5, 0.5], -1.), gray(0.));
+}
+
+#[test]
+fn volume() {
+    let slices = [Texture2D::new(2, 2, vec![gray(0.); 4]), Texture2D::new(2, 2, vec![gray(1.); 4])];
+    let texture = Texture3D::from_slices(&slices);
+    assert_eq!((texture.width(), texture.height(), texture.depth()), (2, 2, 2));
+    assert_eq!(texture.levels(), 2);
+    assert_eq!(texture.level(1).data, vec![gray(0.5)]);
+
+    let sampler = Sampler { wrap: [Wrap::Clamp, Wrap::Clamp], wrap_w: Wrap::Clamp, .. Sampler::new() };
+    assert_eq!(sampler.sample_3d(&texture, [0.5, 0.5, 0.25]), gray(0.));
+    assert_eq!(sampler.sample_3d(&texture, [0.5, 0.5, 0.5]), gray(0.5));
+    assert_eq!(sampler.sample_3d(&texture, [0.5, 0.5, 1.]), gray(1.));
+    assert_eq!(sampler.sample_3d_lod(&texture, [0.5, 0.5, 0.25], 1.), gray(0.5));
+}